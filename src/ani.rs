@@ -0,0 +1,206 @@
+// SPDX-FileCopyrightText: 2026 (c) Gary "BLumia" Wang <opensource@blumia.net>
+//
+// SPDX-License-Identifier: MIT
+
+const ANIHEADER_SIZE: u32 = 36;
+const AF_ICON: u32 = 0x1;
+const AF_SEQUENCE: u32 = 0x2;
+
+/// Default frame duration in jiffies (1/60 s) when `--rate` is not given.
+pub const DEFAULT_RATE: u32 = 10;
+
+/// Builds a RIFF `ACON` animated cursor.
+///
+/// `frames` holds complete ICO/CUR files, `rates` the duration of each frame in
+/// jiffies and `sequence` the optional 0-based order in which frames are shown.
+pub fn build_ani(frames: &[Vec<u8>], rates: &[u32], sequence: &[u32]) -> Vec<u8> {
+    let steps: Vec<u32> = if sequence.is_empty() {
+        (0..frames.len() as u32).collect()
+    } else {
+        sequence.to_vec()
+    };
+    let step_rates: Vec<u32> = steps
+        .iter()
+        .map(|&f| rates.get(f as usize).copied().unwrap_or(DEFAULT_RATE))
+        .collect();
+    let display_rate = step_rates.first().copied().unwrap_or(DEFAULT_RATE);
+    let uniform_rate = step_rates.iter().all(|&r| r == display_rate);
+
+    let mut flags = AF_ICON;
+    if !sequence.is_empty() {
+        flags |= AF_SEQUENCE;
+    }
+
+    let mut body = Vec::new();
+    body.extend_from_slice(b"ACON");
+
+    let mut anih = Vec::with_capacity(ANIHEADER_SIZE as usize);
+    for v in [
+        ANIHEADER_SIZE,
+        frames.len() as u32,
+        steps.len() as u32,
+        0, // iWidth
+        0, // iHeight
+        0, // iBitCount
+        0, // nPlanes
+        display_rate,
+        flags,
+    ] {
+        anih.extend_from_slice(&v.to_le_bytes());
+    }
+    write_chunk(&mut body, b"anih", &anih);
+
+    if !uniform_rate {
+        write_chunk(&mut body, b"rate", &u32_list(&step_rates));
+    }
+    if !sequence.is_empty() {
+        write_chunk(&mut body, b"seq ", &u32_list(sequence));
+    }
+
+    let mut fram = Vec::new();
+    fram.extend_from_slice(b"fram");
+    for frame in frames {
+        write_chunk(&mut fram, b"icon", frame);
+    }
+    write_chunk(&mut body, b"LIST", &fram);
+
+    let mut out = Vec::with_capacity(body.len() + 8);
+    write_chunk(&mut out, b"RIFF", &body);
+    out
+}
+
+pub struct AniFile {
+    /// Complete ICO/CUR files, one per frame.
+    pub frames: Vec<Vec<u8>>,
+    pub steps: u32,
+    pub display_rate: u32,
+    /// Per-step durations from the `rate` chunk, empty if absent.
+    pub rates: Vec<u32>,
+    /// 0-based frame order from the `seq ` chunk, empty if absent.
+    pub sequence: Vec<u32>,
+}
+
+pub fn is_ani(data: &[u8]) -> bool {
+    data.len() >= 12 && &data[0..4] == b"RIFF" && &data[8..12] == b"ACON"
+}
+
+pub fn parse_ani(data: &[u8]) -> Result<AniFile, String> {
+    if !is_ani(data) {
+        return Err("not an animated cursor file".to_string());
+    }
+    let riff_len = read_u32_le(data, 4)? as usize;
+    let body = &data[12..data.len().min(8 + riff_len).max(12)];
+
+    let mut header: Option<(u32, u32, u32, u32)> = None;
+    let mut ani = AniFile {
+        frames: Vec::new(),
+        steps: 0,
+        display_rate: 0,
+        rates: Vec::new(),
+        sequence: Vec::new(),
+    };
+
+    for (id, chunk) in chunks(body)? {
+        match &id {
+            b"anih" => {
+                if chunk.len() < ANIHEADER_SIZE as usize {
+                    return Err("premature end".to_string());
+                }
+                header = Some((
+                    read_u32_le(chunk, 4)?,
+                    read_u32_le(chunk, 8)?,
+                    read_u32_le(chunk, 28)?,
+                    read_u32_le(chunk, 32)?,
+                ));
+            }
+            b"rate" => ani.rates = read_u32_list(chunk),
+            b"seq " => ani.sequence = read_u32_list(chunk),
+            b"LIST" if chunk.starts_with(b"fram") => {
+                for (sub_id, frame) in chunks(&chunk[4..])? {
+                    if &sub_id == b"icon" {
+                        ani.frames.push(frame.to_vec());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let Some((frame_count, steps, display_rate, flags)) = header else {
+        return Err("missing animation header".to_string());
+    };
+    if flags & AF_ICON == 0 {
+        return Err("raw bitmap animation frames not supported".to_string());
+    }
+    if ani.frames.len() != frame_count as usize {
+        return Err("frame count does not match animation header".to_string());
+    }
+    if ani.sequence.iter().any(|&f| f >= frame_count) {
+        return Err("animation sequence refers to missing frame".to_string());
+    }
+    ani.steps = steps;
+    ani.display_rate = display_rate;
+    Ok(ani)
+}
+
+type Chunk<'a> = ([u8; 4], &'a [u8]);
+
+fn chunks(mut data: &[u8]) -> Result<Vec<Chunk<'_>>, String> {
+    let mut out = Vec::new();
+    while data.len() >= 8 {
+        let id: [u8; 4] = data[0..4].try_into().unwrap();
+        let len = read_u32_le(data, 4)? as usize;
+        let chunk = data
+            .get(8..8 + len)
+            .ok_or_else(|| "premature end".to_string())?;
+        out.push((id, chunk));
+        let next = (8 + len + (len & 1)).min(data.len());
+        data = &data[next..];
+    }
+    Ok(out)
+}
+
+fn read_u32_le(data: &[u8], offset: usize) -> Result<u32, String> {
+    let bytes = data
+        .get(offset..offset + 4)
+        .ok_or_else(|| "premature end".to_string())?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn read_u32_list(data: &[u8]) -> Vec<u32> {
+    data.chunks_exact(4)
+        .map(|c| u32::from_le_bytes([c[0], c[1], c[2], c[3]]))
+        .collect()
+}
+
+fn write_chunk(out: &mut Vec<u8>, id: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(id);
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out.extend_from_slice(data);
+    if data.len() % 2 == 1 {
+        out.push(0);
+    }
+}
+
+fn u32_list(values: &[u32]) -> Vec<u8> {
+    values.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ani_round_trips_frames_rates_and_sequence() {
+        let frames = vec![vec![1, 2, 3], vec![4, 5, 6, 7]];
+        let bytes = build_ani(&frames, &[6, 12], &[1, 0, 1]);
+        assert!(is_ani(&bytes));
+
+        let ani = parse_ani(&bytes).unwrap();
+        assert_eq!(ani.frames, frames);
+        assert_eq!(ani.steps, 3);
+        assert_eq!(ani.display_rate, 12);
+        assert_eq!(ani.rates, vec![12, 6, 12]);
+        assert_eq!(ani.sequence, vec![1, 0, 1]);
+    }
+}
@@ -2,7 +2,7 @@
 //
 // SPDX-License-Identifier: MIT
 
-use crate::types::{Action, Command, CreateInput, ParsedArgs};
+use crate::types::{Action, Command, CreateInput, Format, Machine, ParsedArgs};
 use crate::util;
 
 pub fn parse_args(argv: &[String]) -> Result<(Action, Option<ParsedArgs>), String> {
@@ -10,6 +10,7 @@ pub fn parse_args(argv: &[String]) -> Result<(Action, Option<ParsedArgs>), Strin
     let mut output: Option<String> = None;
 
     let mut image_index: i32 = -1;
+    let mut frame: i32 = -1;
     let mut width: i32 = -1;
     let mut height: i32 = -1;
     let mut bit_depth: i32 = -1;
@@ -22,6 +23,18 @@ pub fn parse_args(argv: &[String]) -> Result<(Action, Option<ParsedArgs>), Strin
     let mut icon_only = false;
     let mut cursor_only = false;
     let mut compat_png_bitcount = true;
+    let mut extract_mask = false;
+    let mut template: Option<String> = None;
+    let mut no_clobber = false;
+    let mut sizes: Vec<u32> = Vec::new();
+    let mut png_threshold: i32 = -1;
+    let mut format: Option<Format> = None;
+    let mut format_value = String::new();
+    let mut rate: i32 = -1;
+    let mut sequence: Vec<u32> = Vec::new();
+    let mut machine = Machine::X64;
+    let mut resource_id: u16 = 1;
+    let mut resource_language: u16 = 1033;
 
     let mut files: Vec<String> = Vec::new();
     let mut create_inputs: Vec<CreateInput> = Vec::new();
@@ -39,6 +52,7 @@ pub fn parse_args(argv: &[String]) -> Result<(Action, Option<ParsedArgs>), Strin
                     min_bit_depth: bit_depth,
                     hotspot_x,
                     hotspot_y,
+                    rate,
                 });
             }
             break;
@@ -52,6 +66,7 @@ pub fn parse_args(argv: &[String]) -> Result<(Action, Option<ParsedArgs>), Strin
                 min_bit_depth: bit_depth,
                 hotspot_x,
                 hotspot_y,
+                rate,
             });
             i += 1;
             continue;
@@ -74,6 +89,12 @@ pub fn parse_args(argv: &[String]) -> Result<(Action, Option<ParsedArgs>), Strin
                         &util::take_value(value, argv, &mut i, "--index")?,
                     )?
                 }
+                "frame" => {
+                    frame = util::parse_i32(
+                        "frame",
+                        &util::take_value(value, argv, &mut i, "--frame")?,
+                    )?
+                }
                 "width" => {
                     width = util::parse_i32(
                         "width",
@@ -119,6 +140,62 @@ pub fn parse_args(argv: &[String]) -> Result<(Action, Option<ParsedArgs>), Strin
                     )?
                 }
                 "no-compat-png-bitcount" => compat_png_bitcount = false,
+                "mask" => extract_mask = true,
+                "no-clobber" => no_clobber = true,
+                "template" => template = Some(util::take_value(value, argv, &mut i, "--template")?),
+                "sizes" => {
+                    sizes = util::parse_size_list(
+                        "sizes",
+                        &util::take_value(value, argv, &mut i, "--sizes")?,
+                    )?
+                }
+                "png-threshold" => {
+                    png_threshold = util::parse_i32(
+                        "png-threshold",
+                        &util::take_value(value, argv, &mut i, "--png-threshold")?,
+                    )?
+                }
+                "format" => {
+                    let value = util::take_value(value, argv, &mut i, "--format")?;
+                    format = Some(parse_format(&value)?);
+                    format_value = value;
+                }
+                "rate" => {
+                    rate =
+                        util::parse_i32("rate", &util::take_value(value, argv, &mut i, "--rate")?)?
+                }
+                "sequence" => {
+                    sequence = util::parse_i32_list(
+                        "sequence",
+                        &util::take_value(value, argv, &mut i, "--sequence")?,
+                    )?
+                    .into_iter()
+                    .map(|n| n as u32)
+                    .collect();
+                    if sequence.contains(&0) {
+                        return Err(
+                            "invalid sequence value: frames are numbered from 1".to_string()
+                        );
+                    }
+                }
+                "machine" => {
+                    machine = parse_machine(&util::take_value(value, argv, &mut i, "--machine")?)?
+                }
+                "resource-id" => {
+                    resource_id = parse_u16(
+                        "resource-id",
+                        &util::take_value(value, argv, &mut i, "--resource-id")?,
+                    )?;
+                    if resource_id == 0 {
+                        return Err("invalid resource-id value: 0".to_string());
+                    }
+                }
+                "resource-language" => {
+                    resource_language = parse_u16(
+                        "resource-language",
+                        &util::take_value(value, argv, &mut i, "--resource-language")?,
+                    )?
+                }
                 "raw" => {
                     let raw_path = util::take_value(value, argv, &mut i, "--raw")?;
                     files.push(raw_path.clone());
@@ -128,6 +205,7 @@ pub fn parse_args(argv: &[String]) -> Result<(Action, Option<ParsedArgs>), Strin
                         min_bit_depth: bit_depth,
                         hotspot_x,
                         hotspot_y,
+                        rate,
                     });
                 }
                 _ => return Err(format!("unrecognized option '--{name}'")),
@@ -203,6 +281,7 @@ pub fn parse_args(argv: &[String]) -> Result<(Action, Option<ParsedArgs>), Strin
                         min_bit_depth: bit_depth,
                         hotspot_x,
                         hotspot_y,
+                        rate,
                     });
                 }
                 _ => return Err(format!("invalid option -- '{ch}'")),
@@ -212,6 +291,10 @@ pub fn parse_args(argv: &[String]) -> Result<(Action, Option<ParsedArgs>), Strin
         i += 1;
     }
 
+    // A cursor file is an icon file with --cursor; the last --format wins.
+    if format_value == "cur" {
+        cursor_only = true;
+    }
     if icon_only && cursor_only {
         return Err("only one of --icon and --cursor may be specified".to_string());
     }
@@ -220,12 +303,28 @@ pub fn parse_args(argv: &[String]) -> Result<(Action, Option<ParsedArgs>), Strin
         return Ok((Action::Run, None));
     };
 
+    // Image formats describe extracted entries, container formats created files.
+    if let Some(format) = format {
+        let is_image = matches!(format, Format::Png | Format::Bmp | Format::Dib);
+        if command == Command::Extract && !is_image {
+            return Err(format!(
+                "--format={format_value} cannot be used with --extract"
+            ));
+        }
+        if command == Command::Create && is_image {
+            return Err(format!(
+                "--format={format_value} can only be used with --extract"
+            ));
+        }
+    }
+
     Ok((
         Action::Run,
         Some(ParsedArgs {
             command,
             output,
             image_index,
+            frame,
             width,
             height,
             bit_depth,
@@ -238,12 +337,51 @@ pub fn parse_args(argv: &[String]) -> Result<(Action, Option<ParsedArgs>), Strin
             icon_only,
             cursor_only,
             compat_png_bitcount,
+            extract_mask,
+            template,
+            no_clobber,
+            sizes,
+            png_threshold,
+            format,
+            sequence,
+            machine,
+            resource_id,
+            resource_language,
             files,
             create_inputs,
         }),
     ))
 }
 
+fn parse_format(value: &str) -> Result<Format, String> {
+    match value {
+        "ico" | "cur" => Ok(Format::Ico),
+        "ani" => Ok(Format::Ani),
+        "res" => Ok(Format::Res),
+        "coff" => Ok(Format::Coff),
+        "os2" => Ok(Format::Os2),
+        "png" => Ok(Format::Png),
+        "bmp" => Ok(Format::Bmp),
+        "dib" => Ok(Format::Dib),
+        _ => Err(format!("invalid format value: {value}")),
+    }
+}
+
+fn parse_machine(value: &str) -> Result<Machine, String> {
+    match value {
+        "x86" | "i386" => Ok(Machine::X86),
+        "x64" | "amd64" | "x86_64" => Ok(Machine::X64),
+        "arm64" | "aarch64" => Ok(Machine::Arm64),
+        _ => Err(format!("invalid machine value: {value}")),
+    }
+}
+
+fn parse_u16(field: &str, value: &str) -> Result<u16, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid {field} value: {value}"))
+}
+
 pub fn print_help(program_name: &str) {
     println!("Usage: {program_name} [OPTION]... [FILE]...");
     println!("Convert and create Win32 icon (.ico) and cursor (.cur) files.");
@@ -257,6 +395,7 @@ pub fn print_help(program_name: &str) {
     println!();
     println!("Options:");
     println!("  -i, --index=NUMBER           match index of image (first is 1)");
+    println!("      --frame=NUMBER           match frame of animated cursor (first is 1)");
     println!("  -w, --width=PIXELS           match width of image");
     println!("  -h, --height=PIXELS          match height of image");
     println!("  -p, --palette-size=COUNT     match number of colors in palette (or 0)");
@@ -269,8 +408,34 @@ pub fn print_help(program_name: &str) {
     );
     println!("  -r, --raw=FILENAME           store input file as raw PNG (\"Vista icons\")");
     println!("      --no-compat-png-bitcount write PNG entry bit count from IHDR");
+    println!("      --sizes=LIST             create one image per size (e.g. 16,32,48,256)");
+    println!("                               from each input file");
+    println!("      --png-threshold=PIXELS   store images at least PIXELS wide as PNG and");
+    println!("                               smaller ones as uncompressed BMP");
+    println!("      --format=FORMAT          write FORMAT when creating: ico (default), cur");
+    println!("                               (same as --cursor), ani, res (compiled");
+    println!("                               resource), coff (object file) or os2 (OS/2");
+    println!("                               icon or pointer); when extracting:");
+    println!("                               png (default), bmp or dib (bare bitmap as");
+    println!("                               stored in the icon)");
+    println!("      --machine=ARCH           target of coff output: x86, x64 (default), arm64");
+    println!("      --resource-id=NUMBER     name of the icon group in res and coff output");
+    println!("                               (default is 1)");
+    println!("      --resource-language=ID   language of res and coff resources (default is");
+    println!("                               1033)");
+    println!("      --rate=JIFFIES           set display time of following animation frames");
+    println!("                               in 1/60 seconds (default is 10)");
+    println!("      --sequence=LIST          set order of animation frames (first is 1)");
     println!("      --icon                   match icons only");
     println!("      --cursor                 match cursors only");
+    println!("      --mask                   also extract the AND mask of bitmap entries as");
+    println!("                               a 1-bit NAME_mask.png");
+    println!("      --template=NAME          name extracted files after NAME, in which");
+    println!("                               {{stem}}, {{index}}, {{frame}}, {{width}} ({{w}}),");
+    println!("                               {{height}} ({{h}}), {{bpp}}, {{palette}}, {{kind}},");
+    println!("                               {{hotspot_x}}, {{hotspot_y}} and {{format}} are");
+    println!("                               replaced; directories are created as needed");
+    println!("      --no-clobber             do not overwrite existing files");
     println!("  -o, --output=PATH            where to place extracted files");
     println!();
 }
@@ -0,0 +1,195 @@
+// SPDX-FileCopyrightText: 2026 (c) Gary "BLumia" Wang <opensource@blumia.net>
+//
+// SPDX-License-Identifier: MIT
+
+use crate::{res::Resource, rsrc::build_resource_section, types::Machine};
+
+const FILE_HEADER_SIZE: usize = 20;
+const SECTION_HEADER_SIZE: usize = 40;
+const RELOCATION_SIZE: usize = 10;
+const SYMBOL_SIZE: usize = 18;
+
+const IMAGE_SCN_CNT_INITIALIZED_DATA: u32 = 0x0000_0040;
+const IMAGE_SCN_MEM_READ: u32 = 0x4000_0000;
+const IMAGE_SCN_MEM_WRITE: u32 = 0x8000_0000;
+const IMAGE_SYM_CLASS_STATIC: u8 = 3;
+const IMAGE_SYM_ABSOLUTE: i16 = -1;
+
+fn machine_type(machine: Machine) -> u16 {
+    match machine {
+        Machine::X86 => 0x014c,
+        Machine::X64 => 0x8664,
+        Machine::Arm64 => 0xaa64,
+    }
+}
+
+/// The relocation type that stores an image-relative address.
+fn addr32nb(machine: Machine) -> u16 {
+    match machine {
+        Machine::X86 => 0x0007,
+        Machine::X64 => 0x0003,
+        Machine::Arm64 => 0x0002,
+    }
+}
+
+/// Builds a COFF object file with a single `.rsrc` section holding
+/// `resources`, ready to be passed to a MinGW or MSVC linker.
+pub fn build_coff_object(resources: &[Resource], machine: Machine) -> Vec<u8> {
+    // Data entries hold RVAs, so every one of them is relocated against the
+    // section symbol.
+    let section = build_resource_section(resources, 0);
+    let data_offset = FILE_HEADER_SIZE + SECTION_HEADER_SIZE;
+    let data_size = section.data.len().next_multiple_of(4);
+    let relocations_offset = data_offset + data_size;
+    let relocation_count = section.data_rva_fields.len();
+    let symbols_offset = relocations_offset + relocation_count * RELOCATION_SIZE;
+
+    let mut symbols = Vec::new();
+    // Section symbol with its auxiliary section definition record.
+    write_symbol(
+        &mut symbols,
+        b".rsrc\0\0\0",
+        0,
+        1,
+        IMAGE_SYM_CLASS_STATIC,
+        1,
+    );
+    symbols.extend_from_slice(&(section.data.len() as u32).to_le_bytes());
+    symbols.extend_from_slice(&(relocation_count as u16).to_le_bytes());
+    symbols.extend_from_slice(&[0; 12]);
+    if machine == Machine::X86 {
+        // Declares the object SAFESEH-compatible so `/SAFESEH` links succeed.
+        write_symbol(
+            &mut symbols,
+            b"@feat.00",
+            1,
+            IMAGE_SYM_ABSOLUTE,
+            IMAGE_SYM_CLASS_STATIC,
+            0,
+        );
+    }
+    let symbol_count = symbols.len() / SYMBOL_SIZE;
+
+    let mut out = Vec::with_capacity(symbols_offset + symbols.len() + 4);
+    out.extend_from_slice(&machine_type(machine).to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes());
+    out.extend_from_slice(&0u32.to_le_bytes()); // TimeDateStamp
+    out.extend_from_slice(&(symbols_offset as u32).to_le_bytes());
+    out.extend_from_slice(&(symbol_count as u32).to_le_bytes());
+    out.extend_from_slice(&0u16.to_le_bytes()); // SizeOfOptionalHeader
+    out.extend_from_slice(&0u16.to_le_bytes()); // Characteristics
+
+    out.extend_from_slice(b".rsrc\0\0\0");
+    out.extend_from_slice(&0u32.to_le_bytes()); // VirtualSize
+    out.extend_from_slice(&0u32.to_le_bytes()); // VirtualAddress
+    out.extend_from_slice(&(data_size as u32).to_le_bytes());
+    out.extend_from_slice(&(data_offset as u32).to_le_bytes());
+    out.extend_from_slice(&(relocations_offset as u32).to_le_bytes());
+    out.extend_from_slice(&0u32.to_le_bytes()); // PointerToLinenumbers
+    out.extend_from_slice(&(relocation_count as u16).to_le_bytes());
+    out.extend_from_slice(&0u16.to_le_bytes()); // NumberOfLinenumbers
+    out.extend_from_slice(
+        &(IMAGE_SCN_CNT_INITIALIZED_DATA | IMAGE_SCN_MEM_READ | IMAGE_SCN_MEM_WRITE).to_le_bytes(),
+    );
+
+    out.extend_from_slice(&section.data);
+    out.resize(relocations_offset, 0);
+    for &field in &section.data_rva_fields {
+        out.extend_from_slice(&(field as u32).to_le_bytes());
+        out.extend_from_slice(&0u32.to_le_bytes()); // the section symbol
+        out.extend_from_slice(&addr32nb(machine).to_le_bytes());
+    }
+    out.extend_from_slice(&symbols);
+    // Empty string table: just its own size.
+    out.extend_from_slice(&4u32.to_le_bytes());
+    out
+}
+
+fn write_symbol(
+    out: &mut Vec<u8>,
+    name: &[u8; 8],
+    value: u32,
+    section_number: i16,
+    storage_class: u8,
+    aux_count: u8,
+) {
+    out.extend_from_slice(name);
+    out.extend_from_slice(&value.to_le_bytes());
+    out.extend_from_slice(&section_number.to_le_bytes());
+    out.extend_from_slice(&0u16.to_le_bytes()); // Type
+    out.push(storage_class);
+    out.push(aux_count);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        pe::{read_u16_le, read_u32_le},
+        res::ResId,
+    };
+
+    fn resource(type_id: u16, name: ResId, data: Vec<u8>) -> Resource {
+        Resource {
+            type_id: ResId::Id(type_id),
+            name,
+            language: Some(1033),
+            offset: 0,
+            data,
+        }
+    }
+
+    #[test]
+    fn coff_object_relocates_every_data_entry() {
+        let resources = vec![
+            resource(3, ResId::Id(1), vec![1, 2, 3]),
+            resource(14, ResId::Name("APP".to_string()), vec![4; 6]),
+        ];
+        let obj = build_coff_object(&resources, Machine::X86);
+        let u16_at = |data: &[u8], offset| read_u16_le(data, offset).unwrap() as usize;
+        let u32_at = |data: &[u8], offset| read_u32_le(data, offset).unwrap() as usize;
+
+        assert_eq!(u16_at(&obj, 0), 0x014c);
+        assert_eq!(u16_at(&obj, 2), 1);
+
+        let header = &obj[FILE_HEADER_SIZE..FILE_HEADER_SIZE + SECTION_HEADER_SIZE];
+        assert_eq!(&header[0..8], b".rsrc\0\0\0");
+        let (raw_size, raw_offset) = (u32_at(header, 16), u32_at(header, 20));
+        let (relocations, relocation_count) = (u32_at(header, 24), u16_at(header, 32));
+        assert_eq!(raw_offset, FILE_HEADER_SIZE + SECTION_HEADER_SIZE);
+        assert_eq!(relocations, raw_offset + raw_size);
+        assert_eq!(relocation_count, resources.len());
+        assert_eq!(u32_at(header, 36), 0xc000_0040);
+
+        // Every relocation must point at the OffsetToData field of a data
+        // entry, which with the section at RVA 0 is the offset of the data.
+        let section = &obj[raw_offset..raw_offset + raw_size];
+        let mut relocated = Vec::new();
+        for relocation in obj[relocations..]
+            .chunks_exact(RELOCATION_SIZE)
+            .take(relocation_count)
+        {
+            let field = u32_at(relocation, 0);
+            assert_eq!(u32_at(relocation, 4), 0);
+            assert_eq!(u16_at(relocation, 8), 0x0007);
+            let (rva, size) = (u32_at(section, field), u32_at(section, field + 4));
+            relocated.push(section[rva..rva + size].to_vec());
+        }
+        relocated.sort();
+        assert_eq!(relocated, vec![vec![1, 2, 3], vec![4; 6]]);
+
+        let symbols = u32_at(&obj, 8);
+        assert_eq!(u32_at(&obj, 12), 3);
+        let feat = &obj[symbols + 2 * SYMBOL_SIZE..symbols + 3 * SYMBOL_SIZE];
+        assert_eq!(&feat[0..8], b"@feat.00");
+        assert_eq!(u32_at(feat, 8), 1);
+        assert_eq!(read_u16_le(feat, 12).unwrap() as i16, IMAGE_SYM_ABSOLUTE);
+        assert_eq!(obj.len(), symbols + 3 * SYMBOL_SIZE + 4);
+
+        let obj = build_coff_object(&resources, Machine::X64);
+        assert_eq!(u16_at(&obj, 0), 0x8664);
+        assert_eq!(u32_at(&obj, 12), 2);
+        let relocations = u32_at(&obj, FILE_HEADER_SIZE + 24);
+        assert_eq!(u16_at(&obj, relocations + 8), 0x0003);
+    }
+}
@@ -3,9 +3,19 @@
 // SPDX-License-Identifier: MIT
 
 use crate::{
+    ani::{DEFAULT_RATE, build_ani},
+    coff::build_coff_object,
+    dib::{decode_bmp_file, encode_dib, is_bmp_file},
     input::read_input,
+    os2::{build_os2, is_os2, os2_to_ico},
+    output::write_file,
     parse::{parse_dib_info, parse_png_info},
-    types::{CreateInput, ParsedArgs},
+    res::{
+        RT_CURSOR, RT_GROUP_CURSOR, RT_GROUP_ICON, RT_ICON, ResId, Resource, icon_group_from_ico,
+    },
+    resample::resize_to_square,
+    resfile::build_res_file,
+    types::{CreateInput, Format, ParsedArgs},
 };
 use std::io::{self, IsTerminal, Write};
 
@@ -26,10 +36,16 @@ pub fn run_create(args: &ParsedArgs) -> i32 {
         return 1;
     }
 
-    let is_cursor = args.cursor_only && !args.icon_only;
+    let format = args.format.unwrap_or(Format::Ico);
+    // Animated files are cursors unless explicitly asked for icons.
+    let is_cursor = if format == Format::Ani {
+        !args.icon_only
+    } else {
+        args.cursor_only && !args.icon_only
+    };
     let container_type: u16 = if is_cursor { 2 } else { 1 };
 
-    let mut images: Vec<EncodedImage> = Vec::with_capacity(args.create_inputs.len());
+    let mut frames: Vec<Vec<EncodedImage>> = Vec::with_capacity(args.create_inputs.len());
     for input in &args.create_inputs {
         let bytes = match read_input(&input.path) {
             Ok(b) => b,
@@ -39,17 +55,58 @@ pub fn run_create(args: &ParsedArgs) -> i32 {
             }
         };
 
-        let encoded = match encode_one(input, &bytes) {
+        let encoded = match encode_input(input, &bytes, args) {
             Ok(v) => v,
             Err(msg) => {
                 eprintln!("{}: {msg}", input.path);
                 return 1;
             }
         };
-        images.push(encoded);
+        frames.push(encoded);
     }
 
-    let out_bytes = build_ico(container_type, is_cursor, &images, args.compat_png_bitcount);
+    let out_bytes = match format {
+        Format::Ico => {
+            let images: Vec<EncodedImage> = frames.into_iter().flatten().collect();
+            build_ico(container_type, is_cursor, &images, args.compat_png_bitcount)
+        }
+        Format::Ani => match build_animated(container_type, is_cursor, &frames, args) {
+            Ok(v) => v,
+            Err(msg) => {
+                eprintln!("{msg}");
+                return 1;
+            }
+        },
+        Format::Os2 => {
+            let images: Vec<EncodedImage> = frames.into_iter().flatten().collect();
+            match build_os2(&images, is_cursor, args.alpha_threshold) {
+                Ok(v) => v,
+                Err(msg) => {
+                    eprintln!("{msg}");
+                    return 1;
+                }
+            }
+        }
+        Format::Png | Format::Bmp | Format::Dib => {
+            eprintln!("cannot create image files");
+            return 1;
+        }
+        Format::Res | Format::Coff => {
+            let images: Vec<EncodedImage> = frames.into_iter().flatten().collect();
+            let resources = match build_group_resources(container_type, is_cursor, &images, args) {
+                Ok(v) => v,
+                Err(msg) => {
+                    eprintln!("{msg}");
+                    return 1;
+                }
+            };
+            if format == Format::Res {
+                build_res_file(&resources)
+            } else {
+                build_coff_object(&resources, args.machine)
+            }
+        }
+    };
 
     if write_to_stdout {
         let mut stdout = io::stdout().lock();
@@ -69,61 +126,226 @@ pub fn run_create(args: &ParsedArgs) -> i32 {
         return 1;
     }
 
-    if std::fs::write(out_path, out_bytes).is_err() {
-        eprintln!("{out_path}: cannot write file");
-        return 1;
+    match write_file(out_path, &out_bytes, args.no_clobber) {
+        Ok(()) => {}
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+            eprintln!("{out_path}: file exists");
+            return 1;
+        }
+        Err(_) => {
+            eprintln!("{out_path}: cannot write file");
+            return 1;
+        }
     }
 
     0
 }
 
-struct EncodedImage {
+pub(crate) struct EncodedImage {
+    pub width: u32,
+    pub height: u32,
+    pub bit_depth: u32,
+    pub palette_size: u32,
+    pub hotspot_x: u16,
+    pub hotspot_y: u16,
+    pub data: Vec<u8>,
+}
+
+impl EncodedImage {
+    pub(crate) fn from_data(data: Vec<u8>, hotspot_x: i32, hotspot_y: i32) -> Result<Self, String> {
+        let (width, height, bit_depth, palette_size) = if is_png_bytes(&data) {
+            let (w, h, bpp) = parse_png_info(&data)?;
+            (w, h, bpp, 0)
+        } else {
+            parse_dib_info(&data)?
+        };
+
+        Ok(EncodedImage {
+            width,
+            height,
+            bit_depth,
+            palette_size,
+            hotspot_x: clamp_u16(hotspot_x),
+            hotspot_y: clamp_u16(hotspot_y),
+            data,
+        })
+    }
+}
+
+fn encode_input(
+    input: &CreateInput,
+    bytes: &[u8],
+    args: &ParsedArgs,
+) -> Result<Vec<EncodedImage>, String> {
+    if is_ico_bytes(bytes) {
+        return copy_ico_entries(input, bytes);
+    }
+    if is_os2(bytes) {
+        return copy_ico_entries(input, &os2_to_ico(bytes)?);
+    }
+    if input.raw_png || args.sizes.is_empty() {
+        return Ok(vec![encode_one(input, bytes, args)?]);
+    }
+
+    let (w, h, rgba) = decode_image_rgba(bytes)?;
+    let mut images = Vec::with_capacity(args.sizes.len());
+    for &size in &args.sizes {
+        let resized = resize_to_square(w, h, &rgba, size);
+        let (hotspot_x, hotspot_y) = scale_hotspot(input, w, h, size);
+        let data = encode_rgba(size, size, resized, input.min_bit_depth, args)?;
+        images.push(EncodedImage::from_data(data, hotspot_x, hotspot_y)?);
+    }
+    Ok(images)
+}
+
+/// Copies the entries of an existing icon or cursor file as-is, keeping their
+/// PNG or DIB payloads and, for cursors, their hotspots.
+fn copy_ico_entries(input: &CreateInput, bytes: &[u8]) -> Result<Vec<EncodedImage>, String> {
+    let icon_dir = ico::IconDir::read(std::io::Cursor::new(bytes))
+        .map_err(|_| "not an icon or cursor file".to_string())?;
+
+    let mut images = Vec::with_capacity(icon_dir.entries().len());
+    for entry in icon_dir.entries() {
+        let (hotspot_x, hotspot_y) = match entry.cursor_hotspot() {
+            Some((x, y)) => (x as i32, y as i32),
+            None => (input.hotspot_x, input.hotspot_y),
+        };
+        images.push(EncodedImage::from_data(
+            entry.data().to_vec(),
+            hotspot_x,
+            hotspot_y,
+        )?);
+    }
+    Ok(images)
+}
+
+/// Maps the hotspot of the master image onto a resized entry, following the
+/// same fit-and-center placement as `resize_to_square`.
+fn scale_hotspot(input: &CreateInput, width: u32, height: u32, size: u32) -> (i32, i32) {
+    let scale = f64::min(size as f64 / width as f64, size as f64 / height as f64);
+    let scaled_w = ((width as f64 * scale).round() as i32).clamp(1, size as i32);
+    let scaled_h = ((height as f64 * scale).round() as i32).clamp(1, size as i32);
+    let x0 = (size as i32 - scaled_w) / 2;
+    let y0 = (size as i32 - scaled_h) / 2;
+    let x = x0 + (input.hotspot_x as f64 * scale).round() as i32;
+    let y = y0 + (input.hotspot_y as f64 * scale).round() as i32;
+    (x.min(size as i32 - 1), y.min(size as i32 - 1))
+}
+
+fn encode_one(
+    input: &CreateInput,
+    bytes: &[u8],
+    args: &ParsedArgs,
+) -> Result<EncodedImage, String> {
+    if input.raw_png {
+        parse_png_info(bytes)?;
+        return EncodedImage::from_data(bytes.to_vec(), input.hotspot_x, input.hotspot_y);
+    }
+
+    let (w, h, rgba) = decode_image_rgba(bytes)?;
+    let data = encode_rgba(w, h, rgba, input.min_bit_depth, args)?;
+    EncodedImage::from_data(data, input.hotspot_x, input.hotspot_y)
+}
+
+fn encode_rgba(
     width: u32,
     height: u32,
-    bit_depth: u32,
-    hotspot_x: u16,
-    hotspot_y: u16,
-    data: Vec<u8>,
+    rgba: Vec<u8>,
+    bit_depth: i32,
+    args: &ParsedArgs,
+) -> Result<Vec<u8>, String> {
+    // With --png-threshold, large entries become PNG and everything smaller an
+    // uncompressed DIB, since pre-Vista consumers cannot read PNG entries.
+    let use_png = if args.png_threshold != -1 {
+        Some(width.max(height) >= args.png_threshold as u32)
+    } else {
+        None
+    };
+
+    if bit_depth != -1 && use_png != Some(true) {
+        return encode_dib(width, height, &rgba, bit_depth as u32, args.alpha_threshold);
+    }
+
+    let image = ico::IconImage::from_rgba_data(width, height, rgba);
+    let entry = match use_png {
+        Some(true) => ico::IconDirEntry::encode_as_png(&image),
+        Some(false) => ico::IconDirEntry::encode_as_bmp(&image),
+        None => ico::IconDirEntry::encode(&image),
+    }
+    .map_err(|_| "failed to encode image".to_string())?;
+    Ok(entry.data().to_vec())
 }
 
-fn encode_one(input: &CreateInput, bytes: &[u8]) -> Result<EncodedImage, String> {
-    if input.raw_png {
-        let (w, h, bpp) = parse_png_info(bytes)?;
-        return Ok(EncodedImage {
-            width: w,
-            height: h,
-            bit_depth: bpp,
-            hotspot_x: clamp_u16(input.hotspot_x),
-            hotspot_y: clamp_u16(input.hotspot_y),
-            data: bytes.to_vec(),
-        });
-    }
-
-    let (w, h, rgba) = decode_png_rgba(bytes)?;
-    let image = ico::IconImage::from_rgba_data(w, h, rgba);
-    let entry =
-        ico::IconDirEntry::encode(&image).map_err(|_| "failed to encode image".to_string())?;
-    let data = entry.data().to_vec();
-
-    let (width, height, bit_depth) = if is_png_bytes(&data) {
-        let (w, h, bpp) = parse_png_info(&data)?;
-        (w, h, bpp)
+/// Builds an animated cursor with one frame per input file.
+fn build_animated(
+    container_type: u16,
+    is_cursor: bool,
+    frames: &[Vec<EncodedImage>],
+    args: &ParsedArgs,
+) -> Result<Vec<u8>, String> {
+    let sequence: Vec<u32> = args.sequence.iter().map(|&n| n - 1).collect();
+    if let Some(n) = args.sequence.iter().find(|&&n| n as usize > frames.len()) {
+        return Err(format!("sequence refers to missing frame {n}"));
+    }
+
+    let rates: Vec<u32> = args
+        .create_inputs
+        .iter()
+        .map(|input| {
+            if input.rate == -1 {
+                DEFAULT_RATE
+            } else {
+                input.rate as u32
+            }
+        })
+        .collect();
+    let icons: Vec<Vec<u8>> = frames
+        .iter()
+        .map(|images| build_ico(container_type, is_cursor, images, args.compat_png_bitcount))
+        .collect();
+
+    Ok(build_ani(&icons, &rates, &sequence))
+}
+
+/// Splits the icon or cursor that `build_ico` would write into an
+/// `RT_GROUP_ICON`/`RT_GROUP_CURSOR` resource and its member resources.
+fn build_group_resources(
+    container_type: u16,
+    is_cursor: bool,
+    images: &[EncodedImage],
+    args: &ParsedArgs,
+) -> Result<Vec<Resource>, String> {
+    let ico = build_ico(container_type, is_cursor, images, args.compat_png_bitcount);
+    let group = icon_group_from_ico(&ico, 1)?;
+    let (member_type, group_type) = if is_cursor {
+        (RT_CURSOR, RT_GROUP_CURSOR)
     } else {
-        let (w, h, bpp, _) = parse_dib_info(&data)?;
-        (w, h, bpp)
+        (RT_ICON, RT_GROUP_ICON)
     };
 
-    Ok(EncodedImage {
-        width,
-        height,
-        bit_depth,
-        hotspot_x: clamp_u16(input.hotspot_x),
-        hotspot_y: clamp_u16(input.hotspot_y),
-        data,
-    })
+    let mut resources: Vec<Resource> = group
+        .members
+        .into_iter()
+        .enumerate()
+        .map(|(i, data)| Resource {
+            type_id: ResId::Id(member_type),
+            name: ResId::Id(i as u16 + 1),
+            language: Some(args.resource_language),
+            offset: 0,
+            data,
+        })
+        .collect();
+    resources.push(Resource {
+        type_id: ResId::Id(group_type),
+        name: ResId::Id(args.resource_id),
+        language: Some(args.resource_language),
+        offset: 0,
+        data: group.group,
+    });
+    Ok(resources)
 }
 
-fn build_ico(
+pub(crate) fn build_ico(
     container_type: u16,
     is_cursor: bool,
     images: &[EncodedImage],
@@ -154,7 +376,13 @@ fn build_ico(
         out.push(width_byte);
         out.push(height_byte);
 
-        let color_count: u8 = 0;
+        // bColorCount is the number of color table entries, or 0 when there is
+        // no color table or it has 256 (or more) entries.
+        let color_count: u8 = if img.palette_size < 256 {
+            img.palette_size as u8
+        } else {
+            0
+        };
         out.push(color_count);
         out.push(0);
 
@@ -190,13 +418,28 @@ fn to_dim_byte(dim: u32) -> u8 {
     if dim >= 256 { 0 } else { dim as u8 }
 }
 
-fn is_png_bytes(data: &[u8]) -> bool {
+fn is_ico_bytes(data: &[u8]) -> bool {
+    data.len() >= 6 && data[0..2] == [0, 0] && matches!(data[2..4], [1, 0] | [2, 0])
+}
+
+pub(crate) fn is_png_bytes(data: &[u8]) -> bool {
     const SIG: [u8; 8] = [137, 80, 78, 71, 13, 10, 26, 10];
     data.len() >= SIG.len() && data[..SIG.len()] == SIG
 }
 
-fn decode_png_rgba(data: &[u8]) -> Result<(u32, u32, Vec<u8>), String> {
-    let decoder = png::Decoder::new(std::io::Cursor::new(data));
+fn decode_image_rgba(data: &[u8]) -> Result<(u32, u32, Vec<u8>), String> {
+    if is_bmp_file(data) {
+        decode_bmp_file(data)
+    } else {
+        decode_png_rgba(data)
+    }
+}
+
+pub(crate) fn decode_png_rgba(data: &[u8]) -> Result<(u32, u32, Vec<u8>), String> {
+    let mut decoder = png::Decoder::new(std::io::Cursor::new(data));
+    // Expand palettes, tRNS and bit depths other than 8 so only the four
+    // color types below remain.
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
     let mut reader = decoder
         .read_info()
         .map_err(|_| "not a png file".to_string())?;
@@ -211,7 +454,7 @@ fn decode_png_rgba(data: &[u8]) -> Result<(u32, u32, Vec<u8>), String> {
         png::ColorType::Rgb => rgb_to_rgba(bytes),
         png::ColorType::Grayscale => gray_to_rgba(bytes),
         png::ColorType::GrayscaleAlpha => gray_alpha_to_rgba(bytes),
-        png::ColorType::Indexed => indexed_to_rgba(&mut reader, bytes)?,
+        png::ColorType::Indexed => return Err("failed to decode png".to_string()),
     };
 
     Ok((info.width, info.height, rgba))
@@ -243,32 +486,6 @@ fn gray_alpha_to_rgba(ga: &[u8]) -> Vec<u8> {
     out
 }
 
-fn indexed_to_rgba(
-    reader: &mut png::Reader<std::io::Cursor<&[u8]>>,
-    idx: &[u8],
-) -> Result<Vec<u8>, String> {
-    let palette = reader
-        .info()
-        .palette
-        .as_ref()
-        .ok_or_else(|| "png palette missing".to_string())?;
-    let trns = reader.info().trns.as_deref();
-
-    let mut out = Vec::with_capacity(idx.len() * 4);
-    for &i in idx {
-        let base = (i as usize) * 3;
-        if base + 2 >= palette.len() {
-            return Err("png palette index out of range".to_string());
-        }
-        let r = palette[base];
-        let g = palette[base + 1];
-        let b = palette[base + 2];
-        let a = trns.and_then(|t| t.get(i as usize).copied()).unwrap_or(255);
-        out.extend_from_slice(&[r, g, b, a]);
-    }
-    Ok(out)
-}
-
 fn clamp_u16(v: i32) -> u16 {
     if v <= 0 {
         0
@@ -290,17 +507,10 @@ fn write_u32_le(out: &mut Vec<u8>, v: u32) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_util::{icotool_args, rgba_png};
 
     fn make_rgba_png_1x1() -> Vec<u8> {
-        let mut buf = Vec::new();
-        {
-            let mut encoder = png::Encoder::new(&mut buf, 1, 1);
-            encoder.set_color(png::ColorType::Rgba);
-            encoder.set_depth(png::BitDepth::Eight);
-            let mut writer = encoder.write_header().unwrap();
-            writer.write_image_data(&[0, 0, 0, 0]).unwrap();
-        }
-        buf
+        rgba_png(1, 1, &[0, 0, 0, 0])
     }
 
     fn make_rgba16_png_1x1() -> Vec<u8> {
@@ -315,6 +525,362 @@ mod tests {
         buf
     }
 
+    fn default_args() -> ParsedArgs {
+        icotool_args(&["-c"])
+    }
+
+    fn input_with_bit_depth(bit_depth: i32) -> CreateInput {
+        CreateInput {
+            path: "mem".to_string(),
+            raw_png: false,
+            min_bit_depth: bit_depth,
+            hotspot_x: 0,
+            hotspot_y: 0,
+            rate: -1,
+        }
+    }
+
+    #[test]
+    fn create_with_bit_depth_writes_paletted_dib() {
+        let rgba: Vec<u8> = (0..16u8)
+            .flat_map(|i| [i * 16, 0, 255 - i * 16, 255])
+            .collect();
+        let bytes = rgba_png(4, 4, &rgba);
+
+        for bit_depth in [1, 4, 8, 24, 32] {
+            let img =
+                encode_one(&input_with_bit_depth(bit_depth), &bytes, &default_args()).unwrap();
+            assert!(!is_png_bytes(&img.data));
+            let (w, h, bpp, pal) = parse_dib_info(&img.data).unwrap();
+            assert_eq!((w, h, bpp), (4, 4, bit_depth as u32));
+            let expected_pal = if bit_depth <= 8 { 1 << bit_depth } else { 0 };
+            assert_eq!(pal, expected_pal);
+
+            let ico_bytes = build_ico(1, false, std::slice::from_ref(&img), true);
+            let dir = ico::IconDir::read(std::io::Cursor::new(&ico_bytes)).unwrap();
+            let decoded = dir.entries()[0].decode().unwrap();
+            if bit_depth >= 8 {
+                assert_eq!(decoded.rgba_data(), rgba.as_slice());
+            }
+        }
+    }
+
+    #[test]
+    fn create_from_indexed_png_with_bit_depth() {
+        let mut bytes = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut bytes, 3, 1);
+            encoder.set_color(png::ColorType::Indexed);
+            encoder.set_depth(png::BitDepth::Two);
+            encoder.set_palette(vec![0, 0, 0, 255, 255, 255, 0, 0, 0]);
+            encoder.set_trns(vec![255, 255, 0]);
+            let mut writer = encoder.write_header().unwrap();
+            writer.write_image_data(&[0b0001_1000]).unwrap();
+        }
+
+        let img = encode_one(&input_with_bit_depth(1), &bytes, &default_args()).unwrap();
+        assert_eq!(parse_dib_info(&img.data).unwrap(), (3, 1, 1, 2));
+        let dib = crate::dib::decode_dib(&img.data).unwrap();
+        assert_eq!(dib.rgba, vec![0, 0, 0, 255, 255, 255, 255, 255, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn create_with_bit_depth_masks_transparent_pixels() {
+        let rgba = [255, 0, 0, 255, 0, 255, 0, 0, 0, 0, 255, 255, 9, 9, 9, 255];
+        let bytes = rgba_png(2, 2, &rgba);
+        let img = encode_one(&input_with_bit_depth(1), &bytes, &default_args()).unwrap();
+
+        // header + 2 palette entries + 2 XOR rows + 2 AND rows
+        assert_eq!(img.data.len(), 40 + 8 + 8 + 8);
+        // The palette must contain black for the masked pixel.
+        let palette = &img.data[40..48];
+        assert!(palette.chunks_exact(4).any(|c| c == [0, 0, 0, 0]));
+        // AND mask, bottom-up: the top-right pixel is transparent.
+        assert_eq!(img.data[56], 0);
+        assert_eq!(img.data[60], 0b0100_0000);
+    }
+
+    #[test]
+    fn create_with_bit_depth_uses_alpha_threshold_for_mask() {
+        let rgba = [0, 0, 0, 100, 0, 0, 0, 200];
+        let bytes = rgba_png(2, 1, &rgba);
+
+        let img = encode_one(&input_with_bit_depth(24), &bytes, &default_args()).unwrap();
+        assert_eq!(img.data[40 + 8], 0b1000_0000);
+
+        let img = encode_one(
+            &input_with_bit_depth(24),
+            &bytes,
+            &icotool_args(&["-c", "-t", "50"]),
+        )
+        .unwrap();
+        assert_eq!(img.data[40 + 8], 0);
+
+        let img = encode_one(
+            &input_with_bit_depth(24),
+            &bytes,
+            &icotool_args(&["-c", "-t", "200"]),
+        )
+        .unwrap();
+        assert_eq!(img.data[40 + 8], 0b1100_0000);
+    }
+
+    #[test]
+    fn create_writes_color_count_for_paletted_entries() {
+        let rgba: Vec<u8> = (0..16u8)
+            .flat_map(|i| [i * 16, 0, 255 - i * 16, 255])
+            .collect();
+        let bytes = rgba_png(4, 4, &rgba);
+
+        for (bit_depth, color_count) in [(1, 2), (4, 16), (8, 0), (24, 0), (32, 0)] {
+            let img =
+                encode_one(&input_with_bit_depth(bit_depth), &bytes, &default_args()).unwrap();
+            let ico = build_ico(1, false, &[img], true);
+            assert_eq!(ico[8], color_count);
+            assert_eq!(u16::from_le_bytes([ico[10], ico[11]]), 1);
+            assert_eq!(u16::from_le_bytes([ico[12], ico[13]]), bit_depth as u16);
+        }
+    }
+
+    #[test]
+    fn create_with_sizes_resamples_master_into_each_entry() {
+        let rgba = [200u8, 100, 50, 255].repeat(64 * 64);
+        let bytes = rgba_png(64, 64, &rgba);
+        let args = icotool_args(&["-c", "-b", "8", "--sizes=16,24,32", "mem"]);
+
+        let images = encode_input(&args.create_inputs[0], &bytes, &args).unwrap();
+        let dims: Vec<(u32, u32, u32)> = images
+            .iter()
+            .map(|i| (i.width, i.height, i.bit_depth))
+            .collect();
+        assert_eq!(dims, vec![(16, 16, 8), (24, 24, 8), (32, 32, 8)]);
+    }
+
+    #[test]
+    fn create_with_sizes_scales_cursor_hotspot() {
+        let rgba = [0u8, 0, 0, 255].repeat(64 * 64);
+        let bytes = rgba_png(64, 64, &rgba);
+        let args = icotool_args(&[
+            "-c",
+            "--cursor",
+            "-X",
+            "32",
+            "-Y",
+            "63",
+            "--sizes=16",
+            "mem",
+        ]);
+
+        let images = encode_input(&args.create_inputs[0], &bytes, &args).unwrap();
+        assert_eq!((images[0].hotspot_x, images[0].hotspot_y), (8, 15));
+    }
+
+    fn make_bmp(info_header: &[u8], extra: &[u8], pixels: &[u8]) -> Vec<u8> {
+        let offset = 14 + info_header.len() + extra.len();
+        let mut out = Vec::new();
+        out.extend_from_slice(b"BM");
+        out.extend_from_slice(&((offset + pixels.len()) as u32).to_le_bytes());
+        out.extend_from_slice(&[0; 4]);
+        out.extend_from_slice(&(offset as u32).to_le_bytes());
+        out.extend_from_slice(info_header);
+        out.extend_from_slice(extra);
+        out.extend_from_slice(pixels);
+        out
+    }
+
+    fn make_info_header(
+        size: u32,
+        w: i32,
+        h: i32,
+        bpp: u16,
+        compression: u32,
+        clr_used: u32,
+    ) -> Vec<u8> {
+        let mut hdr = Vec::new();
+        hdr.extend_from_slice(&size.to_le_bytes());
+        hdr.extend_from_slice(&w.to_le_bytes());
+        hdr.extend_from_slice(&h.to_le_bytes());
+        hdr.extend_from_slice(&1u16.to_le_bytes());
+        hdr.extend_from_slice(&bpp.to_le_bytes());
+        hdr.extend_from_slice(&compression.to_le_bytes());
+        hdr.extend_from_slice(&[0; 12]);
+        hdr.extend_from_slice(&clr_used.to_le_bytes());
+        hdr.extend_from_slice(&0u32.to_le_bytes());
+        hdr.resize(size as usize, 0);
+        hdr
+    }
+
+    #[test]
+    fn create_decodes_paletted_bottom_up_bmp() {
+        let hdr = make_info_header(40, 2, 2, 8, 0, 2);
+        let palette = [0, 0, 255, 0, 255, 0, 0, 0];
+        // Bottom row first: [1, 0], then top row [0, 1].
+        let pixels = [1, 0, 0, 0, 0, 1, 0, 0];
+        let bytes = make_bmp(&hdr, &palette, &pixels);
+
+        let (w, h, rgba) = decode_image_rgba(&bytes).unwrap();
+        assert_eq!((w, h), (2, 2));
+        let red = [255, 0, 0, 255];
+        let blue = [0, 0, 255, 255];
+        assert_eq!(rgba, [red, blue, blue, red].concat());
+
+        let img = encode_one(&input_with_bit_depth(8), &bytes, &default_args()).unwrap();
+        assert_eq!((img.width, img.height, img.bit_depth), (2, 2, 8));
+    }
+
+    #[test]
+    fn create_decodes_v5_bmp_with_alpha_bitfields() {
+        let mut hdr = make_info_header(124, 1, -2, 32, 3, 0);
+        for (i, mask) in [0x00ff_0000u32, 0x0000_ff00, 0x0000_00ff, 0xff00_0000]
+            .iter()
+            .enumerate()
+        {
+            hdr[40 + i * 4..44 + i * 4].copy_from_slice(&mask.to_le_bytes());
+        }
+        // Top-down: first stored row is the top row.
+        let pixels = [30, 20, 10, 128, 60, 50, 40, 255];
+        let bytes = make_bmp(&hdr, &[], &pixels);
+
+        let (w, h, rgba) = decode_image_rgba(&bytes).unwrap();
+        assert_eq!((w, h), (1, 2));
+        assert_eq!(rgba, vec![10, 20, 30, 128, 40, 50, 60, 255]);
+    }
+
+    #[test]
+    fn create_copies_entries_of_ico_and_cur_inputs() {
+        let png_bytes = make_rgba_png_1x1();
+        let mut raw = input_with_bit_depth(-1);
+        raw.raw_png = true;
+        raw.hotspot_x = 3;
+        raw.hotspot_y = 4;
+        let png_img = encode_one(&raw, &png_bytes, &default_args()).unwrap();
+        let rgba = [0u8, 0, 0, 255].repeat(4);
+        let dib_img = encode_one(
+            &input_with_bit_depth(4),
+            &rgba_png(2, 2, &rgba),
+            &default_args(),
+        )
+        .unwrap();
+        let png_data = png_img.data.clone();
+        let dib_data = dib_img.data.clone();
+        let cur = build_ico(2, true, &[png_img, dib_img], true);
+
+        let args = icotool_args(&["-c", "--sizes=16", "-b", "8", "mem"]);
+        let images = encode_input(&args.create_inputs[0], &cur, &args).unwrap();
+        assert_eq!(images.len(), 2);
+        assert_eq!(images[0].data, png_data);
+        assert_eq!((images[0].hotspot_x, images[0].hotspot_y), (3, 4));
+        assert_eq!(images[1].data, dib_data);
+        assert_eq!((images[1].width, images[1].bit_depth), (2, 4));
+    }
+
+    #[test]
+    fn create_with_png_threshold_picks_format_per_size() {
+        let mut rgba = [10u8, 20, 30, 255].repeat(256 * 256);
+        rgba[3] = 128;
+        let bytes = rgba_png(256, 256, &rgba);
+        let args = icotool_args(&["-c", "--png-threshold=256", "--sizes=16,48,256", "mem"]);
+
+        let images = encode_input(&args.create_inputs[0], &bytes, &args).unwrap();
+        let is_png: Vec<bool> = images.iter().map(|i| is_png_bytes(&i.data)).collect();
+        assert_eq!(is_png, vec![false, false, true]);
+
+        let args = icotool_args(&[
+            "-c",
+            "--png-threshold=32",
+            "-b",
+            "8",
+            "--sizes=16,32",
+            "mem",
+        ]);
+        let images = encode_input(&args.create_inputs[0], &bytes, &args).unwrap();
+        assert!(!is_png_bytes(&images[0].data));
+        assert_eq!(images[0].bit_depth, 8);
+        assert!(is_png_bytes(&images[1].data));
+    }
+
+    #[test]
+    fn create_format_cur_implies_cursor() {
+        let args = icotool_args(&["-c", "--format=cur", "mem"]);
+        assert!(matches!(args.format, Some(Format::Ico)));
+        assert!(args.cursor_only);
+        let args = icotool_args(&["-c", "--format=cur", "--format=ico", "mem"]);
+        assert!(!args.cursor_only);
+
+        let argv: Vec<String> = ["-c", "--format=cur", "--icon", "mem"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert!(crate::cli::parse_args(&argv).is_err());
+    }
+
+    #[test]
+    fn create_animated_cursor_writes_riff_chunks() {
+        let args = icotool_args(&[
+            "-c",
+            "--format=ani",
+            "-X",
+            "1",
+            "a",
+            "--rate=5",
+            "-X",
+            "2",
+            "b",
+            "--sequence=1,2,1",
+        ]);
+        let png = make_rgba_png_1x1();
+        let frames: Vec<Vec<EncodedImage>> = args
+            .create_inputs
+            .iter()
+            .map(|input| encode_input(input, &png, &args).unwrap())
+            .collect();
+        let ani = build_animated(2, true, &frames, &args).unwrap();
+
+        assert_eq!(&ani[0..4], b"RIFF");
+        assert_eq!(
+            u32::from_le_bytes(ani[4..8].try_into().unwrap()) as usize,
+            ani.len() - 8
+        );
+        assert_eq!(&ani[8..12], b"ACON");
+        assert_eq!(&ani[12..16], b"anih");
+        let anih = &ani[20..56];
+        let field = |i: usize| u32::from_le_bytes(anih[i * 4..i * 4 + 4].try_into().unwrap());
+        assert_eq!((field(1), field(2)), (2, 3));
+        assert_eq!(field(7), DEFAULT_RATE);
+        assert_eq!(field(8), 0x3);
+
+        assert_eq!(&ani[56..60], b"rate");
+        let rates: Vec<u32> = ani[64..76]
+            .chunks_exact(4)
+            .map(|c| u32::from_le_bytes(c.try_into().unwrap()))
+            .collect();
+        assert_eq!(rates, vec![DEFAULT_RATE, 5, DEFAULT_RATE]);
+        assert_eq!(&ani[76..80], b"seq ");
+        assert_eq!(&ani[84..96], &[0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0]);
+
+        assert_eq!(&ani[96..100], b"LIST");
+        assert_eq!(&ani[104..108], b"fram");
+        assert_eq!(&ani[108..112], b"icon");
+        let frame = &ani[116..];
+        assert_eq!(u16::from_le_bytes([frame[2], frame[3]]), 2);
+        assert_eq!(u16::from_le_bytes([frame[10], frame[11]]), 1);
+    }
+
+    #[test]
+    fn create_animated_cursor_rejects_bad_sequence() {
+        let args = icotool_args(&["-c", "--format=ani", "a", "--sequence=1,2"]);
+        let frames = vec![vec![
+            encode_one(&args.create_inputs[0], &make_rgba_png_1x1(), &args).unwrap(),
+        ]];
+        assert!(build_animated(2, true, &frames, &args).is_err());
+    }
+
+    #[test]
+    fn create_with_unsupported_bit_depth_fails() {
+        let bytes = make_rgba_png_1x1();
+        assert!(encode_one(&input_with_bit_depth(16), &bytes, &default_args()).is_err());
+    }
+
     #[test]
     fn create_raw_png_cursor_writes_hotspot_fields() {
         let bytes = make_rgba_png_1x1();
@@ -324,8 +890,9 @@ mod tests {
             min_bit_depth: -1,
             hotspot_x: 7,
             hotspot_y: 9,
+            rate: -1,
         };
-        let img = encode_one(&input, &bytes).unwrap();
+        let img = encode_one(&input, &bytes, &default_args()).unwrap();
         let ico = build_ico(2, true, &[img], true);
 
         assert_eq!(&ico[0..2], &[0, 0]);
@@ -346,8 +913,9 @@ mod tests {
             min_bit_depth: -1,
             hotspot_x: 0,
             hotspot_y: 0,
+            rate: -1,
         };
-        let img = encode_one(&input, &bytes).unwrap();
+        let img = encode_one(&input, &bytes, &default_args()).unwrap();
         let ico = build_ico(1, false, &[img], true);
 
         assert_eq!(u16::from_le_bytes([ico[2], ico[3]]), 1);
@@ -365,8 +933,9 @@ mod tests {
             min_bit_depth: -1,
             hotspot_x: 0,
             hotspot_y: 0,
+            rate: -1,
         };
-        let img = encode_one(&input, &bytes).unwrap();
+        let img = encode_one(&input, &bytes, &default_args()).unwrap();
         let ico = build_ico(1, false, &[img], false);
 
         assert_eq!(u16::from_le_bytes([ico[12], ico[13]]), 64);
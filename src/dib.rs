@@ -0,0 +1,942 @@
+// SPDX-FileCopyrightText: 2026 (c) Gary "BLumia" Wang <opensource@blumia.net>
+//
+// SPDX-License-Identifier: MIT
+
+use crate::quantize::{PaletteMapper, build_palette};
+
+const BITMAPINFOHEADER_SIZE: u32 = 40;
+
+/// Encodes top-down RGBA pixels as an icon DIB (BITMAPINFOHEADER, color table,
+/// XOR bitmap and 1-bit AND mask) with the given bit depth.
+///
+/// Images with more colors than `bit_depth` allows are quantized. Pixels whose
+/// alpha is at or below `alpha_threshold` are marked transparent in the AND mask.
+pub fn encode_dib(
+    width: u32,
+    height: u32,
+    rgba: &[u8],
+    bit_depth: u32,
+    alpha_threshold: i32,
+) -> Result<Vec<u8>, String> {
+    if !matches!(bit_depth, 1 | 4 | 8 | 24 | 32) {
+        return Err(format!("unsupported bit depth: {bit_depth}"));
+    }
+    let pixel_count = (width as usize) * (height as usize);
+    if width == 0 || height == 0 || rgba.len() != pixel_count * 4 {
+        return Err("invalid image dimensions".to_string());
+    }
+
+    let transparent: Vec<bool> = rgba
+        .chunks_exact(4)
+        .map(|px| (px[3] as i32) <= alpha_threshold)
+        .collect();
+
+    let palette = if bit_depth <= 8 {
+        build_dib_palette(rgba, &transparent, 1usize << bit_depth)
+    } else {
+        Vec::new()
+    };
+    let palette_len = if bit_depth <= 8 {
+        1usize << bit_depth
+    } else {
+        0
+    };
+
+    let xor_stride = row_stride(width, bit_depth);
+    let and_stride = row_stride(width, 1);
+    let image_size = (xor_stride + and_stride) * height as usize;
+
+    let mut out = Vec::with_capacity(BITMAPINFOHEADER_SIZE as usize + palette_len * 4 + image_size);
+    write_u32_le(&mut out, BITMAPINFOHEADER_SIZE);
+    write_u32_le(&mut out, width);
+    write_u32_le(&mut out, height * 2);
+    write_u16_le(&mut out, 1);
+    write_u16_le(&mut out, bit_depth as u16);
+    write_u32_le(&mut out, 0);
+    write_u32_le(&mut out, image_size as u32);
+    write_u32_le(&mut out, 0);
+    write_u32_le(&mut out, 0);
+    write_u32_le(&mut out, 0);
+    write_u32_le(&mut out, 0);
+
+    for i in 0..palette_len {
+        let [r, g, b] = palette.get(i).copied().unwrap_or([0, 0, 0]);
+        out.extend_from_slice(&[b, g, r, 0]);
+    }
+
+    let mut mapper = PaletteMapper::new(&palette);
+    let black = palette.iter().position(|c| *c == [0, 0, 0]).unwrap_or(0) as u8;
+    let w = width as usize;
+    for y in (0..height as usize).rev() {
+        let row_start = out.len();
+        out.resize(row_start + xor_stride, 0);
+        let row = &mut out[row_start..];
+        for x in 0..w {
+            let i = y * w + x;
+            let px = &rgba[i * 4..i * 4 + 4];
+            match bit_depth {
+                32 => row[x * 4..x * 4 + 4].copy_from_slice(&[px[2], px[1], px[0], px[3]]),
+                24 if transparent[i] => {}
+                24 => row[x * 3..x * 3 + 3].copy_from_slice(&[px[2], px[1], px[0]]),
+                _ => {
+                    let index = if transparent[i] {
+                        black
+                    } else {
+                        mapper.index_of([px[0], px[1], px[2]])
+                    };
+                    set_packed_index(row, x, bit_depth, index);
+                }
+            }
+        }
+    }
+
+    for y in (0..height as usize).rev() {
+        let row_start = out.len();
+        out.resize(row_start + and_stride, 0);
+        let row = &mut out[row_start..];
+        for x in 0..w {
+            if transparent[y * w + x] {
+                set_packed_index(row, x, 1, 1);
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+fn build_dib_palette(rgba: &[u8], transparent: &[bool], max_colors: usize) -> Vec<[u8; 3]> {
+    let opaque = || {
+        rgba.chunks_exact(4)
+            .zip(transparent)
+            .filter(|(_, t)| !**t)
+            .map(|(px, _)| [px[0], px[1], px[2]])
+    };
+
+    let mut palette = build_palette(opaque(), max_colors);
+    // Masked pixels must use a black XOR color, otherwise they invert the
+    // background instead of leaving it untouched.
+    if transparent.iter().any(|t| *t) && !palette.contains(&[0, 0, 0]) {
+        if palette.len() >= max_colors {
+            palette = build_palette(opaque(), max_colors - 1);
+        }
+        palette.push([0, 0, 0]);
+    }
+    palette
+}
+
+fn set_packed_index(row: &mut [u8], x: usize, bit_depth: u32, index: u8) {
+    match bit_depth {
+        1 => row[x / 8] |= (index & 1) << (7 - x % 8),
+        4 => row[x / 2] |= (index & 0x0f) << (4 * (1 - x % 2)),
+        _ => row[x] = index,
+    }
+}
+
+pub fn row_stride(width: u32, bit_depth: u32) -> usize {
+    ((width as usize) * (bit_depth as usize)).div_ceil(32) * 4
+}
+
+fn write_u16_le(out: &mut Vec<u8>, v: u16) {
+    out.extend_from_slice(&v.to_le_bytes());
+}
+
+fn write_u32_le(out: &mut Vec<u8>, v: u32) {
+    out.extend_from_slice(&v.to_le_bytes());
+}
+
+pub struct DecodedDib {
+    pub width: u32,
+    pub height: u32,
+    pub bit_depth: u32,
+    /// Top-down RGBA pixels, with alpha taken from the AND mask when the
+    /// entry carries no alpha channel of its own.
+    pub rgba: Vec<u8>,
+    /// Top-down AND mask, `true` for transparent pixels.
+    pub mask: Vec<bool>,
+}
+
+/// Decodes an icon DIB into RGBA pixels.
+///
+/// 32-bit entries whose alpha channel is all zero are treated like entries
+/// without alpha, i.e. transparency comes from the AND mask.
+pub fn decode_dib(data: &[u8]) -> Result<DecodedDib, String> {
+    let header = read_header(data, true)?;
+    let w = header.width as usize;
+    let h = header.height as usize;
+    check_bitmap_size(data, header.pixel_offset, &header, header.bit_depth != 32)?;
+
+    let (mut rgba, xor_len) = decode_color(data, header.pixel_offset, &header)?;
+
+    // Some writers omit the AND mask of 32-bit entries entirely.
+    let mask = match read_mask(data, header.pixel_offset + xor_len, &header) {
+        Some(mask) => mask,
+        None if header.bit_depth == 32 => vec![false; w * h],
+        None => return Err("premature end".to_string()),
+    };
+
+    let has_alpha = header.masks[3] != 0 && rgba.chunks_exact(4).any(|px| px[3] != 0);
+    if !has_alpha {
+        for (px, &masked) in rgba.chunks_exact_mut(4).zip(&mask) {
+            px[3] = if masked { 0 } else { 255 };
+        }
+    }
+
+    Ok(DecodedDib {
+        width: header.width,
+        height: header.height,
+        bit_depth: header.bit_depth,
+        rgba,
+        mask,
+    })
+}
+
+/// Counts the pixels of a 32-bit icon DIB whose AND mask bit contradicts its
+/// alpha channel, i.e. masked but fully opaque or unmasked but fully
+/// transparent. Partially transparent pixels may go either way depending on
+/// the threshold used, so they never count. Returns `None` for entries of
+/// other depths and an error for entries too short to hold both bitmaps.
+pub fn mask_alpha_mismatches(data: &[u8]) -> Result<Option<usize>, String> {
+    let header = read_header(data, true)?;
+    if header.bit_depth != 32 {
+        return Ok(None);
+    }
+    check_bitmap_size(data, header.pixel_offset, &header, true)?;
+    let dib = decode_dib(data)?;
+    let count = dib
+        .rgba
+        .chunks_exact(4)
+        .zip(&dib.mask)
+        .filter(|(px, masked)| match px[3] {
+            0 => !**masked,
+            255 => **masked,
+            _ => false,
+        })
+        .count();
+    Ok(Some(count))
+}
+
+pub struct IndexedDib {
+    pub width: u32,
+    pub height: u32,
+    pub bit_depth: u32,
+    pub palette: Vec<[u8; 3]>,
+    /// Top-down palette indices.
+    pub indices: Vec<u8>,
+    /// Top-down AND mask, `true` for transparent pixels.
+    pub mask: Vec<bool>,
+}
+
+/// Decodes a paletted icon DIB into palette indices, or returns `None` for
+/// entries with more than 8 bits per pixel.
+pub fn decode_dib_indexed(data: &[u8]) -> Result<Option<IndexedDib>, String> {
+    let header = read_header(data, true)?;
+    match header.bit_depth {
+        1 | 4 | 8 => {}
+        depth if depth > 8 => return Ok(None),
+        depth => return Err(format!("unsupported bit depth: {depth}")),
+    }
+    check_bitmap_size(data, header.pixel_offset, &header, true)?;
+
+    let (indices, xor_len) = if matches!(header.compression, BI_RLE8 | BI_RLE4) {
+        let (indices, consumed) = decode_rle(data, header.pixel_offset, &header)?;
+        let len = if header.size_image != 0 {
+            header.size_image as usize
+        } else {
+            consumed
+        };
+        (indices, len)
+    } else {
+        let w = header.width as usize;
+        let h = header.height as usize;
+        let stride = row_stride(header.width, header.bit_depth);
+        let mut indices = Vec::with_capacity(w * h);
+        for y in 0..h {
+            let stored_row = if header.top_down { y } else { h - 1 - y };
+            let off = header.pixel_offset + stored_row * stride;
+            let row = data
+                .get(off..off + stride)
+                .ok_or_else(|| "premature end".to_string())?;
+            indices.extend((0..w).map(|x| packed_index(row, x, header.bit_depth)));
+        }
+        (indices, stride * h)
+    };
+
+    let mask = read_mask(data, header.pixel_offset + xor_len, &header)
+        .ok_or_else(|| "premature end".to_string())?;
+    Ok(Some(IndexedDib {
+        width: header.width,
+        height: header.height,
+        bit_depth: header.bit_depth,
+        palette: header.palette,
+        indices,
+        mask,
+    }))
+}
+
+/// Reads the top-down AND mask at `offset`, or `None` when it is cut short.
+fn read_mask(data: &[u8], offset: usize, header: &DibHeader) -> Option<Vec<bool>> {
+    let w = header.width as usize;
+    let h = header.height as usize;
+    let stride = row_stride(header.width, 1);
+    let bits = data.get(offset..offset + stride * h)?;
+
+    let mut mask = vec![false; w * h];
+    for y in 0..h {
+        let row = &bits[(h - 1 - y) * stride..(h - y) * stride];
+        for x in 0..w {
+            mask[y * w + x] = packed_index(row, x, 1) != 0;
+        }
+    }
+    Some(mask)
+}
+
+/// Decodes a `.bmp` file (BITMAPFILEHEADER followed by a BITMAPINFOHEADER,
+/// V4 or V5 header) into top-down RGBA pixels.
+pub fn decode_bmp_file(data: &[u8]) -> Result<(u32, u32, Vec<u8>), String> {
+    if !is_bmp_file(data) {
+        return Err("not a bmp file".to_string());
+    }
+    let pixel_offset = read_u32_le(data, 10)? as usize;
+    let dib = data
+        .get(BITMAPFILEHEADER_SIZE..)
+        .ok_or_else(|| "premature end".to_string())?;
+    let header = read_header(dib, false)?;
+    let pixel_offset = pixel_offset
+        .checked_sub(BITMAPFILEHEADER_SIZE)
+        .ok_or_else(|| "invalid bitmap data offset".to_string())?;
+
+    let (mut rgba, _) = decode_color(dib, pixel_offset, &header)?;
+    // A 32-bit bitmap without an explicit alpha mask usually leaves the
+    // fourth byte zero; only trust it when somebody actually filled it in.
+    let has_alpha = header.masks[3] != 0 && rgba.chunks_exact(4).any(|px| px[3] != 0);
+    if !has_alpha {
+        for px in rgba.chunks_exact_mut(4) {
+            px[3] = 255;
+        }
+    }
+    Ok((header.width, header.height, rgba))
+}
+
+/// Prepends a BITMAPFILEHEADER to a bare DIB, such as an `RT_BITMAP`
+/// resource, turning it into a `.bmp` file.
+pub fn dib_to_bmp_file(dib: &[u8]) -> Result<Vec<u8>, String> {
+    let header = read_header(dib, false)?;
+    Ok(with_file_header(dib, header.pixel_offset))
+}
+
+fn with_file_header(dib: &[u8], pixel_offset: usize) -> Vec<u8> {
+    let file_size = (BITMAPFILEHEADER_SIZE + dib.len()) as u32;
+    let pixel_offset = (BITMAPFILEHEADER_SIZE + pixel_offset) as u32;
+
+    let mut out = Vec::with_capacity(file_size as usize);
+    out.extend_from_slice(b"BM");
+    out.extend_from_slice(&file_size.to_le_bytes());
+    out.extend_from_slice(&[0; 4]);
+    out.extend_from_slice(&pixel_offset.to_le_bytes());
+    out.extend_from_slice(dib);
+    out
+}
+
+/// Turns an icon DIB into a `.bmp` file of the color bitmap alone, keeping
+/// its header, color table, bit depth and compression but dropping the AND
+/// mask.
+pub fn icon_dib_to_bmp_file(dib: &[u8]) -> Result<Vec<u8>, String> {
+    let header = read_header(dib, true)?;
+    let (_, xor_len) = decode_color(dib, header.pixel_offset, &header)?;
+    let end = header.pixel_offset + xor_len;
+    let mut bitmap = dib
+        .get(..end)
+        .ok_or_else(|| "premature end".to_string())?
+        .to_vec();
+
+    let header_size = read_u32_le(dib, 0)? as usize;
+    if header_size == BITMAPCOREHEADER_SIZE {
+        bitmap[6..8].copy_from_slice(&(header.height as u16).to_le_bytes());
+    } else {
+        let height = if header.top_down {
+            -(header.height as i32)
+        } else {
+            header.height as i32
+        };
+        bitmap[8..12].copy_from_slice(&height.to_le_bytes());
+        if header_size >= 24 {
+            bitmap[20..24].copy_from_slice(&(xor_len as u32).to_le_bytes());
+        }
+    }
+    dib_to_bmp_file(&bitmap)
+}
+
+/// Writes top-down RGBA pixels as a 32-bit `.bmp` file.
+pub fn rgba_to_bmp_file(width: u32, height: u32, rgba: &[u8]) -> Vec<u8> {
+    let image_size = width as usize * height as usize * 4;
+    let mut dib = Vec::with_capacity(BITMAPINFOHEADER_SIZE as usize + image_size);
+    write_u32_le(&mut dib, BITMAPINFOHEADER_SIZE);
+    write_u32_le(&mut dib, width);
+    write_u32_le(&mut dib, height);
+    write_u16_le(&mut dib, 1);
+    write_u16_le(&mut dib, 32);
+    write_u32_le(&mut dib, BI_RGB);
+    write_u32_le(&mut dib, image_size as u32);
+    dib.resize(BITMAPINFOHEADER_SIZE as usize, 0);
+
+    let w = width as usize;
+    for row in rgba.chunks_exact(w * 4).rev() {
+        for px in row.chunks_exact(4) {
+            dib.extend_from_slice(&[px[2], px[1], px[0], px[3]]);
+        }
+    }
+    with_file_header(&dib, BITMAPINFOHEADER_SIZE as usize)
+}
+
+pub struct SplitBitmap {
+    pub width: u32,
+    pub height: u32,
+    pub bit_depth: u32,
+    pub palette_size: u32,
+    /// Length of the header and its color table.
+    pub header_len: usize,
+    /// Top-down RGBA pixels, fully opaque.
+    pub rgba: Vec<u8>,
+}
+
+/// Decodes a bitmap whose header is at the start of `header` while its bits
+/// start at `bits_offset` in `data`, as in OS/2 bitmap arrays.
+pub fn decode_split_bitmap(
+    header: &[u8],
+    data: &[u8],
+    bits_offset: usize,
+) -> Result<SplitBitmap, String> {
+    let info = read_header(header, false)?;
+    let (mut rgba, _) = decode_color(data, bits_offset, &info)?;
+    for px in rgba.chunks_exact_mut(4) {
+        px[3] = 255;
+    }
+    Ok(SplitBitmap {
+        width: info.width,
+        height: info.height,
+        bit_depth: info.bit_depth,
+        palette_size: info.palette.len() as u32,
+        header_len: info.pixel_offset,
+        rgba,
+    })
+}
+
+pub fn is_bmp_file(data: &[u8]) -> bool {
+    data.len() >= BITMAPFILEHEADER_SIZE && data.starts_with(b"BM")
+}
+
+const BITMAPFILEHEADER_SIZE: usize = 14;
+const BITMAPCOREHEADER_SIZE: usize = 12;
+const OS2_MIN_HEADER_SIZE: usize = 16;
+
+const BI_RGB: u32 = 0;
+const BI_RLE8: u32 = 1;
+const BI_RLE4: u32 = 2;
+const BI_BITFIELDS: u32 = 3;
+const BI_ALPHABITFIELDS: u32 = 6;
+
+struct DibHeader {
+    width: u32,
+    /// Height of the color bitmap, i.e. half of the stored height in icons.
+    height: u32,
+    top_down: bool,
+    bit_depth: u32,
+    compression: u32,
+    /// Size of the compressed color bitmap, may be zero for uncompressed ones.
+    size_image: u32,
+    /// Red, green, blue and alpha masks for 16 and 32-bit pixels.
+    masks: [u32; 4],
+    palette: Vec<[u8; 3]>,
+    /// Offset of the pixel data right after the header, masks and palette.
+    pixel_offset: usize,
+}
+
+fn read_header(data: &[u8], in_icon: bool) -> Result<DibHeader, String> {
+    let header_size = read_u32_le(data, 0)? as usize;
+    // OS/2 BITMAPCOREHEADER: 16-bit dimensions, no compression and a palette
+    // of RGBTRIPLEs.
+    let is_core = header_size == BITMAPCOREHEADER_SIZE;
+    let (width, height, bit_depth, compression, size_image, clr_used) = if is_core {
+        (
+            read_u16_le(data, 4)? as i32,
+            read_u16_le(data, 6)? as i32,
+            read_u16_le(data, 10)? as u32,
+            BI_RGB,
+            0,
+            0,
+        )
+    } else if header_size >= OS2_MIN_HEADER_SIZE {
+        // OS/2 2.x BITMAPINFOHEADER2 may be cut short after any field; the
+        // missing ones are zero.
+        let field = |offset: usize| {
+            if offset + 4 <= header_size {
+                read_u32_le(data, offset)
+            } else {
+                Ok(0)
+            }
+        };
+        (
+            read_i32_le(data, 4)?,
+            read_i32_le(data, 8)?,
+            read_u16_le(data, 14)? as u32,
+            field(16)?,
+            field(20)?,
+            field(32)?,
+        )
+    } else {
+        return Err("bitmap header is too short".to_string());
+    };
+
+    if width <= 0 || height == 0 {
+        return Err("invalid bitmap dimensions".to_string());
+    }
+    match (compression, bit_depth) {
+        (BI_RGB, _) | (BI_RLE8, 8) | (BI_RLE4, 4) => {}
+        (BI_BITFIELDS | BI_ALPHABITFIELDS, 16 | 32) => {}
+        _ => return Err("unsupported bitmap compression".to_string()),
+    }
+    if height < 0 && matches!(compression, BI_RLE8 | BI_RLE4) {
+        return Err("invalid bitmap dimensions".to_string());
+    }
+    let top_down = height < 0;
+    let height = if in_icon {
+        height.unsigned_abs() / 2
+    } else {
+        height.unsigned_abs()
+    };
+
+    let mut pixel_offset = header_size;
+    let mut masks = match bit_depth {
+        16 => [0x7c00, 0x03e0, 0x001f, 0],
+        32 => [0x00ff_0000, 0x0000_ff00, 0x0000_00ff, 0xff00_0000],
+        _ => [0; 4],
+    };
+    if matches!(compression, BI_BITFIELDS | BI_ALPHABITFIELDS) {
+        let count = if compression == BI_ALPHABITFIELDS {
+            4
+        } else {
+            3
+        };
+        // V2 and later headers carry the masks inside the header itself.
+        let inline = header_size >= BITMAPINFOHEADER_SIZE as usize + count * 4;
+        let base = BITMAPINFOHEADER_SIZE as usize;
+        for (i, mask) in masks.iter_mut().enumerate().take(count) {
+            *mask = read_u32_le(data, base + i * 4)?;
+        }
+        if header_size >= 56 {
+            masks[3] = read_u32_le(data, base + 12)?;
+        } else if count == 3 {
+            masks[3] = 0;
+        }
+        if !inline {
+            pixel_offset += count * 4;
+        }
+    }
+
+    let palette_len = if bit_depth <= 8 {
+        if clr_used != 0 {
+            (clr_used as usize).min(1 << bit_depth)
+        } else {
+            1usize << bit_depth
+        }
+    } else {
+        0
+    };
+    let entry_size = if is_core { 3 } else { 4 };
+    let mut palette = Vec::with_capacity(palette_len);
+    for i in 0..palette_len {
+        let off = pixel_offset + i * entry_size;
+        let c = data
+            .get(off..off + 3)
+            .ok_or_else(|| "premature end".to_string())?;
+        palette.push([c[2], c[1], c[0]]);
+    }
+    // The color table is sized by biClrUsed, which may exceed what the bit
+    // depth can address.
+    let table_len = if bit_depth <= 8 && clr_used != 0 {
+        clr_used as usize
+    } else {
+        palette_len
+    };
+    pixel_offset += table_len * entry_size;
+
+    Ok(DibHeader {
+        width: width as u32,
+        height,
+        top_down,
+        bit_depth,
+        compression,
+        size_image,
+        masks,
+        palette,
+        pixel_offset,
+    })
+}
+
+/// Upper bound on how far RLE data expands: a 2-byte run covers at most 255
+/// pixels. Delta codes may skip further, but a stream made mostly of skips is
+/// not worth sizing unbounded buffers for.
+const RLE_MAX_PIXELS_PER_BYTE: usize = 128;
+
+/// Checks that the bitmap described by `header`, stored at `offset` and
+/// followed by its AND mask when `with_mask` is set, fits in `data`.
+///
+/// Buffers are sized from the header dimensions, so this must run before any
+/// of them is allocated.
+fn check_bitmap_size(
+    data: &[u8],
+    offset: usize,
+    header: &DibHeader,
+    with_mask: bool,
+) -> Result<(), String> {
+    let available = data.len().saturating_sub(offset);
+    let h = header.height as usize;
+    let color_len = if matches!(header.compression, BI_RLE8 | BI_RLE4) {
+        // Compressed data has no fixed size; bound the expansion instead.
+        (header.width as usize)
+            .checked_mul(h)
+            .map(|pixels| pixels / RLE_MAX_PIXELS_PER_BYTE)
+    } else {
+        row_stride(header.width, header.bit_depth).checked_mul(h)
+    };
+    let mask_len = if with_mask {
+        row_stride(header.width, 1).checked_mul(h)
+    } else {
+        Some(0)
+    };
+    match color_len.zip(mask_len).and_then(|(c, m)| c.checked_add(m)) {
+        Some(len) if len <= available => Ok(()),
+        Some(_) => Err("premature end".to_string()),
+        None => Err("invalid bitmap dimensions".to_string()),
+    }
+}
+
+/// Decodes the color bitmap at `offset` into top-down RGBA, returning it with
+/// the number of bytes it occupies.
+fn decode_color(
+    data: &[u8],
+    offset: usize,
+    header: &DibHeader,
+) -> Result<(Vec<u8>, usize), String> {
+    check_bitmap_size(data, offset, header, false)?;
+    if !matches!(header.compression, BI_RLE8 | BI_RLE4) {
+        let len = row_stride(header.width, header.bit_depth)
+            .checked_mul(header.height as usize)
+            .ok_or_else(|| "invalid bitmap dimensions".to_string())?;
+        return Ok((decode_pixels(data, offset, header)?, len));
+    }
+
+    let (indices, consumed) = decode_rle(data, offset, header)?;
+    let rgba = indices
+        .iter()
+        .flat_map(|&i| {
+            let [r, g, b] = header.palette.get(i as usize).copied().unwrap_or([0, 0, 0]);
+            [r, g, b, 255]
+        })
+        .collect();
+    let len = if header.size_image != 0 {
+        header.size_image as usize
+    } else {
+        consumed
+    };
+    Ok((rgba, len))
+}
+
+/// Expands BI_RLE8 or BI_RLE4 data into top-down palette indices, returning
+/// them with the length of the compressed stream. Pixels the stream skips
+/// keep index 0.
+fn decode_rle(data: &[u8], offset: usize, header: &DibHeader) -> Result<(Vec<u8>, usize), String> {
+    let w = header.width as usize;
+    let h = header.height as usize;
+    let rle4 = header.compression == BI_RLE4;
+    let nibble = |byte: u8, i: usize| if i % 2 == 0 { byte >> 4 } else { byte & 0x0f };
+
+    let mut indices = vec![0u8; w * h];
+    let mut set = |x: usize, y: usize, v: u8| {
+        // Rows are stored bottom-up.
+        if x < w && y < h {
+            indices[(h - 1 - y) * w + x] = v;
+        }
+    };
+
+    let (mut x, mut y) = (0usize, 0usize);
+    let mut pos = offset;
+    // Tolerate streams that end without an end-of-bitmap marker.
+    while let Some(&[count, value]) = data.get(pos..pos + 2) {
+        pos += 2;
+        if count > 0 {
+            for i in 0..count as usize {
+                set(x + i, y, if rle4 { nibble(value, i) } else { value });
+            }
+            x += count as usize;
+            continue;
+        }
+        match value {
+            0 => {
+                x = 0;
+                y += 1;
+            }
+            1 => break,
+            2 => {
+                let delta = data
+                    .get(pos..pos + 2)
+                    .ok_or_else(|| "premature end".to_string())?;
+                x += delta[0] as usize;
+                y += delta[1] as usize;
+                pos += 2;
+            }
+            n => {
+                let n = n as usize;
+                let len = if rle4 { n.div_ceil(2) } else { n };
+                let run = data
+                    .get(pos..pos + len)
+                    .ok_or_else(|| "premature end".to_string())?;
+                for i in 0..n {
+                    set(x + i, y, if rle4 { nibble(run[i / 2], i) } else { run[i] });
+                }
+                x += n;
+                // Absolute runs are padded to a 16-bit boundary.
+                pos += len.next_multiple_of(2);
+            }
+        }
+    }
+    Ok((indices, pos - offset))
+}
+
+/// Decodes uncompressed pixel rows starting at `offset` into top-down RGBA.
+fn decode_pixels(data: &[u8], offset: usize, header: &DibHeader) -> Result<Vec<u8>, String> {
+    let w = header.width as usize;
+    let h = header.height as usize;
+    let bit_depth = header.bit_depth;
+    let stride = row_stride(header.width, bit_depth);
+    let channels = header.masks.map(MaskChannel::new);
+
+    let mut rgba = vec![0u8; w * h * 4];
+    for y in 0..h {
+        let stored_row = if header.top_down { y } else { h - 1 - y };
+        let off = offset + stored_row * stride;
+        let row = data
+            .get(off..off + stride)
+            .ok_or_else(|| "premature end".to_string())?;
+        for x in 0..w {
+            let px = match bit_depth {
+                1 | 4 | 8 => {
+                    let index = packed_index(row, x, bit_depth) as usize;
+                    let [r, g, b] = header.palette.get(index).copied().unwrap_or([0, 0, 0]);
+                    [r, g, b, 255]
+                }
+                16 => {
+                    let v = u16::from_le_bytes([row[x * 2], row[x * 2 + 1]]) as u32;
+                    unpack_masked(v, &channels)
+                }
+                24 => [row[x * 3 + 2], row[x * 3 + 1], row[x * 3], 255],
+                32 => {
+                    let v = u32::from_le_bytes([
+                        row[x * 4],
+                        row[x * 4 + 1],
+                        row[x * 4 + 2],
+                        row[x * 4 + 3],
+                    ]);
+                    unpack_masked(v, &channels)
+                }
+                _ => return Err(format!("unsupported bit depth: {bit_depth}")),
+            };
+            let i = (y * w + x) * 4;
+            rgba[i..i + 4].copy_from_slice(&px);
+        }
+    }
+    Ok(rgba)
+}
+
+#[derive(Clone, Copy)]
+struct MaskChannel {
+    mask: u32,
+    shift: u32,
+    max: u32,
+}
+
+impl MaskChannel {
+    fn new(mask: u32) -> Self {
+        if mask == 0 {
+            return Self {
+                mask,
+                shift: 0,
+                max: 0,
+            };
+        }
+        let shift = mask.trailing_zeros();
+        Self {
+            mask,
+            shift,
+            max: mask >> shift,
+        }
+    }
+
+    fn extract(&self, v: u32) -> Option<u8> {
+        if self.max == 0 {
+            return None;
+        }
+        let raw = ((v & self.mask) >> self.shift) as u64;
+        Some(((raw * 255 + self.max as u64 / 2) / self.max as u64) as u8)
+    }
+}
+
+fn unpack_masked(v: u32, channels: &[MaskChannel; 4]) -> [u8; 4] {
+    [
+        channels[0].extract(v).unwrap_or(0),
+        channels[1].extract(v).unwrap_or(0),
+        channels[2].extract(v).unwrap_or(0),
+        channels[3].extract(v).unwrap_or(255),
+    ]
+}
+
+fn packed_index(row: &[u8], x: usize, bit_depth: u32) -> u8 {
+    match bit_depth {
+        1 => (row[x / 8] >> (7 - x % 8)) & 1,
+        4 => (row[x / 2] >> (4 * (1 - x % 2))) & 0x0f,
+        _ => row[x],
+    }
+}
+
+fn read_u16_le(data: &[u8], offset: usize) -> Result<u16, String> {
+    let bytes = data
+        .get(offset..offset + 2)
+        .ok_or_else(|| "premature end".to_string())?;
+    Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+fn read_u32_le(data: &[u8], offset: usize) -> Result<u32, String> {
+    let bytes = data
+        .get(offset..offset + 4)
+        .ok_or_else(|| "premature end".to_string())?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn read_i32_le(data: &[u8], offset: usize) -> Result<i32, String> {
+    Ok(read_u32_le(data, offset)? as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::parse_dib_info;
+
+    fn info_header(width: i32, height: i32, bit_depth: u16, compression: u32) -> Vec<u8> {
+        let mut h = Vec::with_capacity(40);
+        h.extend(40u32.to_le_bytes());
+        h.extend(width.to_le_bytes());
+        h.extend((height * 2).to_le_bytes());
+        h.extend(1u16.to_le_bytes());
+        h.extend(bit_depth.to_le_bytes());
+        h.extend(compression.to_le_bytes());
+        h.extend([0; 20]);
+        h
+    }
+
+    const RED: [u8; 4] = [255, 0, 0, 255];
+    const GREEN: [u8; 4] = [0, 255, 0, 255];
+    const BLUE: [u8; 4] = [0, 0, 255, 255];
+
+    #[test]
+    fn decodes_rle8_with_absolute_runs_and_mask() {
+        let mut dib = info_header(4, 2, 8, BI_RLE8);
+        let stream = [4, 1, 0, 0, 0, 3, 0, 1, 0, 0, 1, 1, 0, 1];
+        dib[20..24].copy_from_slice(&(stream.len() as u32).to_le_bytes());
+        dib[32..36].copy_from_slice(&2u32.to_le_bytes());
+        dib.extend([0, 0, 255, 0, 0, 255, 0, 0]);
+        dib.extend(stream);
+        dib.extend([0, 0, 0, 0, 0x80, 0, 0, 0]);
+
+        assert_eq!(parse_dib_info(&dib).unwrap(), (4, 2, 8, 2));
+        let decoded = decode_dib(&dib).unwrap();
+        let mut expected = vec![255, 0, 0, 0];
+        expected.extend([GREEN, RED, GREEN, GREEN, GREEN, GREEN, GREEN].concat());
+        assert_eq!(decoded.rgba, expected);
+    }
+
+    #[test]
+    fn decodes_rle4_without_size_image() {
+        let mut dib = info_header(4, 1, 4, BI_RLE4);
+        dib.extend([0, 0, 255, 0, 0, 255, 0, 0]);
+        dib.extend([0; 14 * 4]);
+        dib.extend([4, 0x10, 0, 1]);
+        dib.extend([0; 4]);
+
+        assert_eq!(parse_dib_info(&dib).unwrap(), (4, 1, 4, 16));
+        let decoded = decode_dib(&dib).unwrap();
+        assert_eq!(decoded.rgba, [GREEN, RED, GREEN, RED].concat());
+    }
+
+    #[test]
+    fn decodes_rgb565_bitfields() {
+        let mut dib = info_header(2, 1, 16, BI_BITFIELDS);
+        for mask in [0xf800u32, 0x07e0, 0x001f] {
+            dib.extend(mask.to_le_bytes());
+        }
+        dib.extend([0x00, 0xf8, 0x1f, 0x00]);
+        dib.extend([0; 4]);
+
+        // Like icoutils, 16-bit entries report a palette size of 1 << 16.
+        assert_eq!(parse_dib_info(&dib).unwrap(), (2, 1, 16, 1 << 16));
+        let decoded = decode_dib(&dib).unwrap();
+        assert_eq!(decoded.rgba, [RED, BLUE].concat());
+    }
+
+    #[test]
+    fn decodes_core_header_with_rgbtriple_palette() {
+        let mut dib = 12u32.to_le_bytes().to_vec();
+        for v in [2u16, 2, 1, 4] {
+            dib.extend(v.to_le_bytes());
+        }
+        let mut palette = [[0u8; 3]; 16];
+        palette[1] = [0, 0, 255];
+        palette[2] = [255, 0, 0];
+        dib.extend(palette.concat());
+        dib.extend([0x12, 0, 0, 0]);
+        dib.extend([0x40, 0, 0, 0]);
+
+        assert_eq!(parse_dib_info(&dib).unwrap(), (2, 1, 4, 16));
+        let decoded = decode_dib(&dib).unwrap();
+        assert_eq!(decoded.rgba, [RED, [0, 0, 255, 0]].concat());
+    }
+
+    #[test]
+    fn counts_mask_and_alpha_disagreements() {
+        let rgba = [1, 2, 3, 255, 4, 5, 6, 0, 7, 8, 9, 100];
+        let mut dib = encode_dib(3, 1, &rgba, 32, 127).unwrap();
+        // The half-transparent pixel is masked by the threshold, which is
+        // not a contradiction.
+        assert_eq!(mask_alpha_mismatches(&dib).unwrap(), Some(0));
+
+        // Unmask the fully transparent pixel and mask the opaque one.
+        let and_offset = 40 + 3 * 4;
+        dib[and_offset] &= !0x40;
+        assert_eq!(mask_alpha_mismatches(&dib).unwrap(), Some(1));
+        dib[and_offset] |= 0x80;
+        assert_eq!(mask_alpha_mismatches(&dib).unwrap(), Some(2));
+
+        let paletted = encode_dib(3, 1, &rgba, 8, 127).unwrap();
+        assert_eq!(mask_alpha_mismatches(&paletted).unwrap(), None);
+    }
+
+    #[test]
+    fn rejects_dimensions_larger_than_the_entry() {
+        let mut dib = info_header(0x7fff_ffff, 0x3fff_ffff, 32, BI_RGB);
+        assert_eq!(decode_dib(&dib).err().unwrap(), "premature end");
+
+        dib[14..16].copy_from_slice(&8u16.to_le_bytes());
+        dib[16..20].copy_from_slice(&BI_RLE8.to_le_bytes());
+        dib.extend([0; 256 * 4]);
+        assert!(decode_dib_indexed(&dib).is_err());
+    }
+
+    #[test]
+    fn rejects_2bpp_entries() {
+        let mut dib = info_header(4, 1, 2, BI_RGB);
+        dib.extend([0; 4 * 4]);
+        dib.extend([0b00_01_10_11, 0, 0, 0]);
+        dib.extend([0; 4]);
+        assert!(decode_dib_indexed(&dib).is_err());
+        assert!(decode_dib(&dib).is_err());
+    }
+}
@@ -3,9 +3,17 @@
 // SPDX-License-Identifier: MIT
 
 use crate::{
+    ani::{is_ani, parse_ani},
+    create::decode_png_rgba,
+    dib::{
+        IndexedDib, decode_dib, decode_dib_indexed, encode_dib, icon_dib_to_bmp_file,
+        rgba_to_bmp_file,
+    },
     input::read_input,
+    os2::{is_os2, os2_to_ico},
+    output::{OutputNames, write_file},
     parse::{parse_dib_info, parse_png_info},
-    types::{EntryMeta, ParsedArgs},
+    types::{EntryMeta, Format, ParsedArgs},
 };
 use std::io::{self, Write};
 
@@ -15,6 +23,7 @@ pub fn run_extract(args: &ParsedArgs) -> i32 {
         return 1;
     }
 
+    let mut names = OutputNames::default();
     for file in &args.files {
         let display_name = if file == "-" {
             "(standard in)"
@@ -30,7 +39,7 @@ pub fn run_extract(args: &ParsedArgs) -> i32 {
             }
         };
 
-        let matched = match extract_from_bytes(&bytes, display_name, args) {
+        let matched = match extract_from_bytes(&bytes, display_name, args, &mut names) {
             Ok(matched) => matched,
             Err(msg) => {
                 eprintln!("{display_name}: {msg}");
@@ -46,7 +55,34 @@ pub fn run_extract(args: &ParsedArgs) -> i32 {
     0
 }
 
-fn extract_from_bytes(bytes: &[u8], inname: &str, args: &ParsedArgs) -> Result<usize, String> {
+fn extract_from_bytes(
+    bytes: &[u8],
+    inname: &str,
+    args: &ParsedArgs,
+    names: &mut OutputNames,
+) -> Result<usize, String> {
+    if is_os2(bytes) {
+        return extract_icon_dir(&os2_to_ico(bytes)?, 0, inname, args, names);
+    }
+    if !is_ani(bytes) {
+        return extract_icon_dir(bytes, 0, inname, args, names);
+    }
+
+    let ani = parse_ani(bytes)?;
+    let mut matched = 0usize;
+    for (i, frame) in ani.frames.iter().enumerate() {
+        matched += extract_icon_dir(frame, (i + 1) as i32, inname, args, names)?;
+    }
+    Ok(matched)
+}
+
+fn extract_icon_dir(
+    bytes: &[u8],
+    frame: i32,
+    inname: &str,
+    args: &ParsedArgs,
+    names: &mut OutputNames,
+) -> Result<usize, String> {
     let cursor = std::io::Cursor::new(bytes);
     let icon_dir =
         ico::IconDir::read(cursor).map_err(|_| "not an icon or cursor file".to_string())?;
@@ -54,30 +90,191 @@ fn extract_from_bytes(bytes: &[u8], inname: &str, args: &ParsedArgs) -> Result<u
     let mut matched = 0usize;
     for (i, entry) in icon_dir.entries().iter().enumerate() {
         let index = (i + 1) as i32;
-        let meta = entry_to_meta(index, entry)?;
+        let mut meta = entry_to_meta(index, entry)?;
+        meta.frame = frame;
         if !matches_filters(args, &meta) {
             continue;
         }
         matched += 1;
 
-        let (mut out, outname) = open_extract_output(inname, &args.output, &meta)?;
-        if entry.is_png() {
-            out.write_all(entry.data())
-                .map_err(|_| format!("{outname}: cannot write to file"))?;
-        } else {
-            let image = entry
-                .decode()
-                .map_err(|_| "failed to decode bitmap entry".to_string())?;
-            image
-                .write_png(&mut out)
-                .map_err(|_| format!("{outname}: cannot write to file"))?;
+        let format = args.format.unwrap_or(Format::Png);
+        let data = convert_entry(entry, format, args.alpha_threshold)?;
+        let ext = match format {
+            Format::Bmp => "bmp",
+            Format::Dib => "dib",
+            _ => "png",
+        };
+        let Some(outname) = extract_output_path(inname, args, &meta, ext)? else {
+            if args.extract_mask {
+                return Err("cannot write mask to standard output".to_string());
+            }
+            let mut out = io::stdout().lock();
+            out.write_all(&data)
+                .map_err(|_| "(standard out): cannot write to file".to_string())?;
+            out.flush().ok();
+            continue;
+        };
+        let outname = names.claim(&outname);
+        if !write_output(&outname, &data, args)? {
+            continue;
+        }
+
+        if args.extract_mask && !entry.is_png() {
+            let dib = decode_dib(entry.data())?;
+            let png = encode_mask_png(dib.width, dib.height, &dib.mask)?;
+            let mask_name = names.claim(&mask_path(&outname));
+            write_output(&mask_name, &png, args)?;
         }
-        out.flush().ok();
     }
 
     Ok(matched)
 }
 
+/// Converts an entry into `format`. DIB entries keep their bit depth and
+/// palette in `.bmp` and DIB output, PNG entries become 32-bit bitmaps.
+fn convert_entry(
+    entry: &ico::IconDirEntry,
+    format: Format,
+    alpha_threshold: i32,
+) -> Result<Vec<u8>, String> {
+    match format {
+        Format::Bmp | Format::Dib if entry.is_png() => {
+            let (w, h, rgba) = decode_png_rgba(entry.data())?;
+            if format == Format::Bmp {
+                Ok(rgba_to_bmp_file(w, h, &rgba))
+            } else {
+                encode_dib(w, h, &rgba, 32, alpha_threshold)
+            }
+        }
+        Format::Bmp => icon_dib_to_bmp_file(entry.data()),
+        Format::Dib => Ok(entry.data().to_vec()),
+        _ if entry.is_png() => Ok(entry.data().to_vec()),
+        _ => {
+            if let Some(dib) = decode_dib_indexed(entry.data())? {
+                if let Some(png) = encode_indexed_png(&dib)? {
+                    return Ok(png);
+                }
+            }
+            let dib = decode_dib(entry.data())?;
+            let image = ico::IconImage::from_rgba_data(dib.width, dib.height, dib.rgba);
+            let mut png = Vec::new();
+            image
+                .write_png(&mut png)
+                .map_err(|_| "failed to encode image".to_string())?;
+            Ok(png)
+        }
+    }
+}
+
+/// Writes a paletted DIB as an indexed PNG with its own color table, marking
+/// one palette entry that no opaque pixel uses as transparent in `tRNS` for
+/// the pixels of the AND mask. When every entry is taken, the PNG grows to
+/// the next bit depth to make room; `None` means not even 8 bits suffice.
+fn encode_indexed_png(dib: &IndexedDib) -> Result<Option<Vec<u8>>, String> {
+    let mut palette = dib.palette.clone();
+    let max_index = dib.indices.iter().copied().max().unwrap_or(0) as usize;
+    if palette.len() <= max_index {
+        palette.resize(max_index + 1, [0, 0, 0]);
+    }
+
+    let mut bit_depth = dib.bit_depth;
+    let mut indices = dib.indices.clone();
+    let mut trns = Vec::new();
+    if dib.mask.iter().any(|&m| m) {
+        let mut used = [false; 256];
+        for (&i, &masked) in dib.indices.iter().zip(&dib.mask) {
+            used[i as usize] |= !masked;
+        }
+        let Some(transparent) = (0..256).find(|&i| !used[i]) else {
+            return Ok(None);
+        };
+        while transparent >= 1 << bit_depth {
+            bit_depth = if bit_depth == 1 { 2 } else { bit_depth * 2 };
+        }
+        if palette.len() <= transparent {
+            palette.resize(transparent + 1, [0, 0, 0]);
+        }
+        trns = vec![255; transparent];
+        trns.push(0);
+        for (i, &masked) in indices.iter_mut().zip(&dib.mask) {
+            if masked {
+                *i = transparent as u8;
+            }
+        }
+    }
+
+    let w = dib.width as usize;
+    let stride = (w * bit_depth as usize).div_ceil(8);
+    let per_byte = 8 / bit_depth as usize;
+    let mut packed = vec![0u8; stride * dib.height as usize];
+    for (y, row) in indices.chunks_exact(w).enumerate() {
+        for (x, &i) in row.iter().enumerate() {
+            let shift = 8 - bit_depth as usize * (x % per_byte + 1);
+            packed[y * stride + x / per_byte] |= i << shift;
+        }
+    }
+
+    let mut out = Vec::new();
+    let mut encoder = png::Encoder::new(&mut out, dib.width, dib.height);
+    encoder.set_color(png::ColorType::Indexed);
+    let depth = png::BitDepth::from_u8(bit_depth as u8)
+        .ok_or_else(|| format!("unsupported bit depth: {bit_depth}"))?;
+    encoder.set_depth(depth);
+    encoder.set_palette(palette.concat());
+    if !trns.is_empty() {
+        encoder.set_trns(trns);
+    }
+    let mut writer = encoder
+        .write_header()
+        .map_err(|_| "failed to encode image".to_string())?;
+    writer
+        .write_image_data(&packed)
+        .map_err(|_| "failed to encode image".to_string())?;
+    writer
+        .finish()
+        .map_err(|_| "failed to encode image".to_string())?;
+    Ok(Some(out))
+}
+
+/// Writes an AND mask as a 1-bit grayscale PNG, white where the mask makes
+/// the image transparent.
+fn encode_mask_png(width: u32, height: u32, mask: &[bool]) -> Result<Vec<u8>, String> {
+    let w = width as usize;
+    let stride = w.div_ceil(8);
+    let mut packed = vec![0u8; stride * height as usize];
+    for (y, row) in mask.chunks_exact(w).enumerate() {
+        for (x, &masked) in row.iter().enumerate() {
+            if masked {
+                packed[y * stride + x / 8] |= 0x80 >> (x % 8);
+            }
+        }
+    }
+
+    let mut out = Vec::new();
+    let mut encoder = png::Encoder::new(&mut out, width, height);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::One);
+    let mut writer = encoder
+        .write_header()
+        .map_err(|_| "failed to encode image".to_string())?;
+    writer
+        .write_image_data(&packed)
+        .map_err(|_| "failed to encode image".to_string())?;
+    writer
+        .finish()
+        .map_err(|_| "failed to encode image".to_string())?;
+    Ok(out)
+}
+
+/// Names the mask of an extracted image `NAME_mask.png` next to it.
+fn mask_path(outname: &str) -> String {
+    let path = std::path::Path::new(outname);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{stem}_mask.png"))
+        .to_string_lossy()
+        .to_string()
+}
+
 fn entry_to_meta(index: i32, entry: &ico::IconDirEntry) -> Result<EntryMeta, String> {
     let is_icon = entry.resource_type() == ico::ResourceType::Icon;
     let (width, height, bit_depth, palette_size) = if entry.is_png() {
@@ -94,6 +291,7 @@ fn entry_to_meta(index: i32, entry: &ico::IconDirEntry) -> Result<EntryMeta, Str
     };
 
     Ok(EntryMeta {
+        frame: 0,
         index,
         width,
         height,
@@ -109,6 +307,9 @@ fn matches_filters(args: &ParsedArgs, meta: &EntryMeta) -> bool {
     if args.image_index != -1 && meta.index != args.image_index {
         return false;
     }
+    if args.frame != -1 && meta.frame != args.frame {
+        return false;
+    }
     if args.width != -1 && meta.width != args.width {
         return false;
     }
@@ -141,45 +342,73 @@ fn matches_filters(args: &ParsedArgs, meta: &EntryMeta) -> bool {
     true
 }
 
-fn open_extract_output(
+/// Writes one extracted file, returning false when --no-clobber kept an
+/// existing file.
+fn write_output(path: &str, data: &[u8], args: &ParsedArgs) -> Result<bool, String> {
+    match write_file(path, data, args.no_clobber) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+            eprintln!("{path}: file exists, skipping");
+            Ok(false)
+        }
+        Err(_) => Err(format!("{path}: cannot create file")),
+    }
+}
+
+/// Picks the file an entry is extracted to, or `None` for standard output.
+fn extract_output_path(
     inname: &str,
-    output: &Option<String>,
+    args: &ParsedArgs,
     meta: &EntryMeta,
-) -> Result<(Box<dyn Write>, String), String> {
-    let Some(output) = output.as_deref() else {
-        let path = gen_extract_name(inname, None, meta);
-        let f = std::fs::File::create(&path).map_err(|_| format!("{path}: cannot create file"))?;
-        return Ok((Box::new(f), path));
+    ext: &str,
+) -> Result<Option<String>, String> {
+    // With a template, --output names the directory the template is relative to.
+    if let Some(template) = args.template.as_deref() {
+        if args.output.as_deref() == Some("-") {
+            return Err("cannot use --template with standard output".to_string());
+        }
+        let name = expand_template(template, inname, meta, ext)?;
+        let path = match args.output.as_deref() {
+            Some(dir) => std::path::Path::new(dir).join(name),
+            None => std::path::PathBuf::from(name),
+        };
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)
+                .map_err(|_| format!("{}: cannot create directory", parent.display()))?;
+        }
+        return Ok(Some(path.to_string_lossy().to_string()));
+    }
+
+    let Some(output) = args.output.as_deref() else {
+        return Ok(Some(gen_extract_name(inname, None, meta, ext)));
     };
 
     let output_is_dir = std::fs::metadata(output)
         .map(|m| m.is_dir())
         .unwrap_or(false);
     if output_is_dir {
-        let path = gen_extract_name(inname, Some(output), meta);
-        let f = std::fs::File::create(&path).map_err(|_| format!("{path}: cannot create file"))?;
-        return Ok((Box::new(f), path));
+        return Ok(Some(gen_extract_name(inname, Some(output), meta, ext)));
     }
 
     if output == "-" {
-        return Ok((Box::new(io::stdout().lock()), "(standard out)".to_string()));
+        return Ok(None);
     }
-
-    let f = std::fs::File::create(output).map_err(|_| format!("{output}: cannot create file"))?;
-    Ok((Box::new(f), output.to_string()))
+    Ok(Some(output.to_string()))
 }
 
-fn gen_extract_name(inname: &str, output_dir: Option<&str>, meta: &EntryMeta) -> String {
-    let mut base = inname;
-    if let Some(pos) = inname.rfind(['/', '\\']) {
-        base = &inname[pos + 1..];
-    }
-
-    let stem = strip_ico_cur_ext(base);
-    let filename = format!(
-        "{stem}_{}_{}x{}x{}.png",
-        meta.index, meta.width, meta.height, meta.bit_depth
-    );
+fn gen_extract_name(inname: &str, output_dir: Option<&str>, meta: &EntryMeta, ext: &str) -> String {
+    let stem = input_stem(inname);
+    let filename = if meta.frame > 0 {
+        format!(
+            "{stem}_f{}_{}_{}x{}x{}.{ext}",
+            meta.frame, meta.index, meta.width, meta.height, meta.bit_depth
+        )
+    } else {
+        format!(
+            "{stem}_{}_{}x{}x{}.{ext}",
+            meta.index, meta.width, meta.height, meta.bit_depth
+        )
+    };
 
     match output_dir {
         Some(dir) => std::path::Path::new(dir)
@@ -190,9 +419,73 @@ fn gen_extract_name(inname: &str, output_dir: Option<&str>, meta: &EntryMeta) ->
     }
 }
 
+/// Expands the `{placeholder}`s of an output name template for one entry.
+/// `{{` and `}}` stand for literal braces.
+fn expand_template(
+    template: &str,
+    inname: &str,
+    meta: &EntryMeta,
+    ext: &str,
+) -> Result<String, String> {
+    let mut out = String::new();
+    let mut chars = template.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+                out.push('{');
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+                out.push('}');
+            }
+            '{' => {
+                let mut name = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => name.push(c),
+                        None => return Err("unterminated placeholder in template".to_string()),
+                    }
+                }
+                let value = match name.as_str() {
+                    "stem" => input_stem(inname).to_string(),
+                    "index" => meta.index.to_string(),
+                    "frame" => meta.frame.to_string(),
+                    "width" | "w" => meta.width.to_string(),
+                    "height" | "h" => meta.height.to_string(),
+                    "bpp" => meta.bit_depth.to_string(),
+                    "palette" => meta.palette_size.to_string(),
+                    "kind" => if meta.is_icon { "icon" } else { "cursor" }.to_string(),
+                    "hotspot_x" => meta.hotspot_x.to_string(),
+                    "hotspot_y" => meta.hotspot_y.to_string(),
+                    "format" => ext.to_string(),
+                    _ => return Err(format!("unknown placeholder {{{name}}} in template")),
+                };
+                out.push_str(&value);
+            }
+            '}' => return Err("unmatched '}' in template".to_string()),
+            c => out.push(c),
+        }
+    }
+    Ok(out)
+}
+
+/// The base name of an input file without its icon or cursor extension.
+fn input_stem(inname: &str) -> &str {
+    let base = match inname.rfind(['/', '\\']) {
+        Some(pos) => &inname[pos + 1..],
+        None => inname,
+    };
+    strip_ico_cur_ext(base)
+}
+
 fn strip_ico_cur_ext(name: &str) -> &str {
     let lower = name.to_ascii_lowercase();
-    if lower.ends_with(".ico") || lower.ends_with(".cur") {
+    if [".ico", ".cur", ".ani", ".ptr"]
+        .iter()
+        .any(|ext| lower.ends_with(ext))
+    {
         &name[..name.len() - 4]
     } else {
         name
@@ -202,18 +495,7 @@ fn strip_ico_cur_ext(name: &str) -> &str {
 #[cfg(test)]
 mod tests {
     use super::*;
-
-    fn make_rgba_png_1x1() -> Vec<u8> {
-        let mut buf = Vec::new();
-        {
-            let mut encoder = png::Encoder::new(&mut buf, 1, 1);
-            encoder.set_color(png::ColorType::Rgba);
-            encoder.set_depth(png::BitDepth::Eight);
-            let mut writer = encoder.write_header().unwrap();
-            writer.write_image_data(&[1, 2, 3, 4]).unwrap();
-        }
-        buf
-    }
+    use crate::test_util::rgba_png;
 
     fn build_ico_with_png(png_bytes: &[u8], width: u8, height: u8) -> Vec<u8> {
         let mut out = Vec::new();
@@ -236,7 +518,7 @@ mod tests {
 
     #[test]
     fn extract_raw_png_entry_writes_original_png_bytes() {
-        let png_bytes = make_rgba_png_1x1();
+        let png_bytes = rgba_png(1, 1, &[1, 2, 3, 4]);
         let ico_bytes = build_ico_with_png(&png_bytes, 1, 1);
 
         let cursor = std::io::Cursor::new(&ico_bytes);
@@ -244,14 +526,129 @@ mod tests {
         assert!(icon_dir.entries()[0].is_png());
 
         let meta = entry_to_meta(1, &icon_dir.entries()[0]).unwrap();
-        let name = gen_extract_name("a/b/c.ico", None, &meta);
+        let name = gen_extract_name("a/b/c.ico", None, &meta, "png");
         assert_eq!(name, "c_1_1x1x32.png");
-        let name2 = gen_extract_name(r"a\b\c.CUR", Some("outdir"), &meta);
+        let name2 = gen_extract_name(r"a\b\c.CUR", Some("outdir"), &meta, "png");
         let path2 = std::path::Path::new(&name2);
         assert_eq!(path2.file_name().unwrap(), "c_1_1x1x32.png");
         assert!(path2.parent().unwrap().ends_with("outdir"));
 
         let data = icon_dir.entries()[0].data();
         assert_eq!(data, png_bytes.as_slice());
+
+        let mut frame_meta = meta.clone();
+        frame_meta.frame = 3;
+        let name3 = gen_extract_name("busy.ani", None, &frame_meta, "png");
+        assert_eq!(name3, "busy_f3_1_1x1x32.png");
+    }
+
+    #[test]
+    fn extract_dib_without_alpha_rebuilds_alpha_from_and_mask() {
+        let rgba = [10, 20, 30, 0, 40, 50, 60, 255];
+        let data = crate::dib::encode_dib(2, 1, &rgba, 24, 127).unwrap();
+        let dib = decode_dib(&data).unwrap();
+        assert_eq!(dib.rgba, vec![0, 0, 0, 0, 40, 50, 60, 255]);
+
+        // A 32-bit entry whose alpha channel is all zero is treated as
+        // having no alpha channel at all.
+        let mut data = crate::dib::encode_dib(2, 1, &rgba, 32, 127).unwrap();
+        data[40 + 7] = 0;
+        let dib = decode_dib(&data).unwrap();
+        assert_eq!(dib.rgba[3], 0);
+        assert_eq!(dib.rgba[7], 255);
+
+        // Faint alpha, however low, is kept.
+        data[40 + 7] = 20;
+        let dib = decode_dib(&data).unwrap();
+        assert_eq!(dib.rgba[7], 20);
+    }
+
+    #[test]
+    fn extract_dib_entry_as_bmp_keeps_bit_depth_and_palette() {
+        let rgba = [255, 0, 0, 255, 0, 0, 255, 255];
+        let data = encode_dib(2, 1, &rgba, 4, 0).unwrap();
+        let image = crate::create::EncodedImage::from_data(data.clone(), 0, 0).unwrap();
+        let ico_bytes = crate::create::build_ico(1, false, &[image], true);
+        let icon_dir = ico::IconDir::read(std::io::Cursor::new(ico_bytes)).unwrap();
+        let entry = &icon_dir.entries()[0];
+
+        let bmp = convert_entry(entry, Format::Bmp, 0).unwrap();
+        // File header, info header, 16 colors and a single 4-byte row.
+        assert_eq!(bmp.len(), 14 + 40 + 16 * 4 + 4);
+        assert_eq!(&bmp[14 + 8..14 + 12], &1i32.to_le_bytes());
+        assert_eq!(&bmp[14 + 14..14 + 16], &4u16.to_le_bytes());
+        assert_eq!(&bmp[54..54 + 16 * 4], &data[40..40 + 16 * 4]);
+        let (w, h, decoded) = crate::dib::decode_bmp_file(&bmp).unwrap();
+        assert_eq!((w, h), (2, 1));
+        assert_eq!(decoded, rgba);
+
+        assert_eq!(convert_entry(entry, Format::Dib, 0).unwrap(), data);
+    }
+
+    #[test]
+    fn extract_template_expands_placeholders() {
+        let meta = EntryMeta {
+            frame: 2,
+            index: 3,
+            width: 32,
+            height: 24,
+            bit_depth: 8,
+            palette_size: 256,
+            is_icon: false,
+            hotspot_x: 4,
+            hotspot_y: 5,
+        };
+        let name = expand_template(
+            "{stem}/{w}x{h}@{bpp}-{kind}{{{index}.{frame}}}_{hotspot_x},{hotspot_y}_{palette}.{format}",
+            "dir/app.cur",
+            &meta,
+            "png",
+        )
+        .unwrap();
+        assert_eq!(name, "app/32x24@8-cursor{3.2}_4,5_256.png");
+
+        assert!(expand_template("{size}.png", "a.ico", &meta, "png").is_err());
+        assert!(expand_template("{stem", "a.ico", &meta, "png").is_err());
+    }
+
+    #[test]
+    fn extract_mask_writes_one_bit_png() {
+        let png = encode_mask_png(3, 2, &[true, false, false, false, false, true]).unwrap();
+        let reader = png::Decoder::new(std::io::Cursor::new(&png))
+            .read_info()
+            .unwrap();
+        assert_eq!(reader.info().bit_depth, png::BitDepth::One);
+        let (_, _, rgba) = decode_png_rgba(&png).unwrap();
+        let white: Vec<bool> = rgba.chunks_exact(4).map(|px| px[0] == 255).collect();
+        assert_eq!(white, [true, false, false, false, false, true]);
+
+        assert_eq!(mask_path("out/a_1_16x16x8.png"), "out/a_1_16x16x8_mask.png");
+        assert_eq!(mask_path("icon.bmp"), "icon_mask.png");
+    }
+
+    #[test]
+    fn extract_paletted_dib_writes_indexed_png() {
+        // Black, white, transparent, white: both palette entries of the 1-bit
+        // entry are taken, so the transparent pixel needs a 2-bit PNG.
+        let rgba = [
+            0, 0, 0, 255, 255, 255, 255, 255, 0, 0, 0, 0, 255, 255, 255, 255,
+        ];
+        let data = encode_dib(2, 2, &rgba, 1, 0).unwrap();
+        let image = crate::create::EncodedImage::from_data(data, 0, 0).unwrap();
+        let ico_bytes = crate::create::build_ico(1, false, &[image], true);
+        let icon_dir = ico::IconDir::read(std::io::Cursor::new(ico_bytes)).unwrap();
+
+        let png = convert_entry(&icon_dir.entries()[0], Format::Png, 0).unwrap();
+        let reader = png::Decoder::new(std::io::Cursor::new(&png))
+            .read_info()
+            .unwrap();
+        let info = reader.info();
+        assert_eq!(info.color_type, png::ColorType::Indexed);
+        assert_eq!(info.bit_depth, png::BitDepth::Two);
+        assert_eq!(info.palette.as_deref().unwrap().len(), 3 * 3);
+        assert_eq!(info.trns.as_deref(), Some(&[255, 255, 0][..]));
+
+        let (_, _, decoded) = decode_png_rgba(&png).unwrap();
+        assert_eq!(decoded, rgba);
     }
 }
@@ -2,14 +2,31 @@
 //
 // SPDX-License-Identifier: MIT
 
+pub mod ani;
 pub mod cli;
+pub mod coff;
 pub mod create;
+pub mod dib;
 pub mod extract;
 pub mod input;
 pub mod list;
+pub mod ne;
+pub mod os2;
+pub mod output;
 pub mod parse;
+pub mod pe;
+pub mod quantize;
+pub mod res;
+pub mod resample;
+pub mod resfile;
+pub mod rsrc;
 pub mod types;
 pub mod util;
+pub mod version_info;
+pub mod wrestool;
+
+#[cfg(test)]
+mod test_util;
 
 use types::{Action, Command};
 
@@ -3,11 +3,24 @@
 // SPDX-License-Identifier: MIT
 
 use crate::{
+    ani::{AniFile, is_ani, parse_ani},
+    dib::mask_alpha_mismatches,
     input::read_input,
+    os2::{is_os2, os2_to_ico},
     parse::{parse_dib_info, parse_png_info},
+    res::{RT_GROUP_CURSOR, RT_GROUP_ICON, ResId, format_resource_id, group_to_ico},
+    resfile::{is_res_file, read_res_file},
     types::{EntryMeta, ParsedArgs},
 };
 
+/// A line of `--list` output. Entries go to stdout so that every line there
+/// selects one image; notes describing the container go to stderr.
+#[derive(Debug, PartialEq)]
+pub enum ListLine {
+    Entry(String),
+    Note(String),
+}
+
 pub fn run_list(args: &ParsedArgs) -> i32 {
     if args.files.is_empty() {
         eprintln!("missing file argument");
@@ -42,14 +55,77 @@ pub fn run_list(args: &ParsedArgs) -> i32 {
         }
 
         for line in lines {
-            println!("{line}");
+            match line {
+                ListLine::Entry(line) => println!("{line}"),
+                ListLine::Note(line) => eprintln!("{line}"),
+            }
         }
     }
 
     0
 }
 
-pub fn list_from_bytes(bytes: &[u8], args: &ParsedArgs) -> Result<(usize, Vec<String>), String> {
+pub fn list_from_bytes(bytes: &[u8], args: &ParsedArgs) -> Result<(usize, Vec<ListLine>), String> {
+    if is_ani(bytes) {
+        return list_ani(bytes, args);
+    }
+    if is_res_file(bytes) {
+        return list_res(bytes, args);
+    }
+    if is_os2(bytes) {
+        let (matched, lines) = list_icon_dir(&os2_to_ico(bytes)?, 0, args)?;
+        let mut out = vec![ListLine::Note("--format=os2".to_string())];
+        out.extend(lines);
+        return Ok((matched, out));
+    }
+    list_icon_dir(bytes, 0, args)
+}
+
+/// Lists the icon and cursor groups of a `.res` file, each introduced by a
+/// note with the wrestool options that select it.
+fn list_res(bytes: &[u8], args: &ParsedArgs) -> Result<(usize, Vec<ListLine>), String> {
+    let resources = read_res_file(bytes)?;
+
+    let mut matched = 0usize;
+    let mut lines = Vec::new();
+    for group in resources.iter().filter(|r| {
+        matches!(
+            r.type_id,
+            ResId::Id(RT_GROUP_ICON) | ResId::Id(RT_GROUP_CURSOR)
+        )
+    }) {
+        let ico = group_to_ico(group, &resources)?;
+        let (group_matched, group_lines) = list_icon_dir(&ico, 0, args)?;
+        matched += group_matched;
+        lines.push(ListLine::Note(format!(
+            "--format=res {}",
+            format_resource_id(group)
+        )));
+        lines.extend(group_lines);
+    }
+
+    Ok((matched, lines))
+}
+
+fn list_ani(bytes: &[u8], args: &ParsedArgs) -> Result<(usize, Vec<ListLine>), String> {
+    let ani = parse_ani(bytes)?;
+
+    let mut matched = 0usize;
+    let mut lines = vec![ListLine::Note(format_ani_line(&ani))];
+    for (i, frame) in ani.frames.iter().enumerate() {
+        let (frame_matched, frame_lines) = list_icon_dir(frame, (i + 1) as i32, args)?;
+        matched += frame_matched;
+        lines.extend(frame_lines);
+    }
+
+    Ok((matched, lines))
+}
+
+fn list_icon_dir(
+    bytes: &[u8],
+    frame: i32,
+    args: &ParsedArgs,
+) -> Result<(usize, Vec<ListLine>), String> {
     let cursor = std::io::Cursor::new(bytes);
     let icon_dir =
         ico::IconDir::read(cursor).map_err(|_| "not an icon or cursor file".to_string())?;
@@ -58,10 +134,24 @@ pub fn list_from_bytes(bytes: &[u8], args: &ParsedArgs) -> Result<(usize, Vec<St
     let mut lines = Vec::new();
     for (i, entry) in icon_dir.entries().iter().enumerate() {
         let index = (i + 1) as i32;
-        let meta = entry_to_meta(index, entry)?;
+        let mut meta = entry_to_meta(index, entry)?;
+        meta.frame = frame;
         if matches_filters(args, &meta) {
             matched += 1;
-            lines.push(format_list_line(&meta));
+            lines.push(ListLine::Entry(format_list_line(&meta)));
+            // Only a hint for 32-bit entries; those that are cut short or fail
+            // to decode are listed as usual.
+            let mismatches = if entry.is_png() || meta.bit_depth != 32 {
+                None
+            } else {
+                mask_alpha_mismatches(entry.data()).ok().flatten()
+            };
+            if let Some(n) = mismatches.filter(|&n| n > 0) {
+                lines.push(ListLine::Note(format!(
+                    "{}: AND mask contradicts alpha in {n} pixels",
+                    format_entry_position(&meta)
+                )));
+            }
         }
     }
 
@@ -84,6 +174,7 @@ fn entry_to_meta(index: i32, entry: &ico::IconDirEntry) -> Result<EntryMeta, Str
     };
 
     Ok(EntryMeta {
+        frame: 0,
         index,
         width,
         height,
@@ -99,6 +190,9 @@ fn matches_filters(args: &ParsedArgs, meta: &EntryMeta) -> bool {
     if args.image_index != -1 && meta.index != args.image_index {
         return false;
     }
+    if args.frame != -1 && meta.frame != args.frame {
+        return false;
+    }
     if args.width != -1 && meta.width != args.width {
         return false;
     }
@@ -131,12 +225,62 @@ fn matches_filters(args: &ParsedArgs, meta: &EntryMeta) -> bool {
     true
 }
 
+/// Formats the create options that rebuild `ani`. Create takes one `--rate`
+/// per input frame, so per-step rates are folded back onto the frames, each
+/// frame keeping the rate of its first step; frames are written as
+/// `FRAME1`, `FRAME2`, ... placeholders when their rates differ.
+fn format_ani_line(ani: &AniFile) -> String {
+    let mut line = "--format=ani".to_string();
+    if !ani.sequence.is_empty() {
+        let sequence: Vec<String> = ani.sequence.iter().map(|f| (f + 1).to_string()).collect();
+        line.push_str(&format!(" --sequence={}", sequence.join(",")));
+    }
+
+    let steps: Vec<u32> = if ani.sequence.is_empty() {
+        (0..ani.frames.len() as u32).collect()
+    } else {
+        ani.sequence.clone()
+    };
+    let mut frame_rates: Vec<Option<u32>> = vec![None; ani.frames.len()];
+    for (step, &frame) in steps.iter().enumerate() {
+        let rate = ani.rates.get(step).copied().unwrap_or(ani.display_rate);
+        if let Some(slot) = frame_rates.get_mut(frame as usize) {
+            slot.get_or_insert(rate);
+        }
+    }
+    let frame_rates: Vec<u32> = frame_rates
+        .into_iter()
+        .map(|rate| rate.unwrap_or(ani.display_rate))
+        .collect();
+
+    if frame_rates.iter().all(|&rate| rate == ani.display_rate) {
+        line.push_str(&format!(" --rate={}", ani.display_rate));
+    } else {
+        for (i, rate) in frame_rates.iter().enumerate() {
+            line.push_str(&format!(" --rate={rate} FRAME{}", i + 1));
+        }
+    }
+    line
+}
+
+fn format_entry_position(meta: &EntryMeta) -> String {
+    if meta.frame > 0 {
+        format!("frame {} image {}", meta.frame, meta.index)
+    } else {
+        format!("image {}", meta.index)
+    }
+}
+
 fn format_list_line(meta: &EntryMeta) -> String {
     let kind = if meta.is_icon { "icon" } else { "cursor" };
-    let mut line = format!(
-        "--{} --index={} --width={} --height={} --bit-depth={} --palette-size={}",
-        kind, meta.index, meta.width, meta.height, meta.bit_depth, meta.palette_size
-    );
+    let mut line = format!("--{kind}");
+    if meta.frame > 0 {
+        line.push_str(&format!(" --frame={}", meta.frame));
+    }
+    line.push_str(&format!(
+        " --index={} --width={} --height={} --bit-depth={} --palette-size={}",
+        meta.index, meta.width, meta.height, meta.bit_depth, meta.palette_size
+    ));
     if !meta.is_icon {
         line.push_str(&format!(
             " --hotspot-x={} --hotspot-y={}",
@@ -149,7 +293,7 @@ fn format_list_line(meta: &EntryMeta) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::Command;
+    use crate::test_util::icotool_args;
 
     #[test]
     fn list_single_icon_entry() {
@@ -159,28 +303,34 @@ mod tests {
         let mut bytes = Vec::new();
         dir.write(&mut bytes).unwrap();
 
-        let args = ParsedArgs {
-            command: Command::List,
-            output: None,
-            image_index: -1,
-            width: -1,
-            height: -1,
-            bit_depth: -1,
-            palette_size: -1,
-            hotspot_x: 0,
-            hotspot_y: 0,
-            hotspot_x_set: false,
-            hotspot_y_set: false,
-            alpha_threshold: 127,
-            icon_only: false,
-            cursor_only: false,
-            compat_png_bitcount: true,
-            files: vec![],
-            create_inputs: vec![],
-        };
+        let args = icotool_args(&["-l"]);
 
         let (matched, lines) = list_from_bytes(&bytes, &args).unwrap();
         assert_eq!(matched, 1);
-        assert!(lines[0].starts_with("--icon --index=1 --width=16 --height=16 "));
+        let ListLine::Entry(line) = &lines[0] else {
+            panic!("expected an entry line");
+        };
+        assert!(line.starts_with("--icon --index=1 --width=16 --height=16 "));
+
+        let ani = crate::ani::build_ani(&[bytes.clone(), bytes.clone()], &[10, 10], &[1, 0]);
+        let (matched, lines) = list_from_bytes(&ani, &args).unwrap();
+        assert_eq!(matched, 2);
+        assert_eq!(
+            lines[0],
+            ListLine::Note("--format=ani --sequence=2,1 --rate=10".to_string())
+        );
+
+        let ani = crate::ani::build_ani(&[bytes.clone(), bytes], &[5, 20], &[1, 0, 1]);
+        let (_, lines) = list_from_bytes(&ani, &args).unwrap();
+        assert_eq!(
+            lines[0],
+            ListLine::Note(
+                "--format=ani --sequence=2,1,2 --rate=5 FRAME1 --rate=20 FRAME2".to_string()
+            )
+        );
+        let ListLine::Entry(line) = &lines[2] else {
+            panic!("expected an entry line");
+        };
+        assert!(line.starts_with("--icon --frame=2 --index=1 --width=16 "));
     }
 }
@@ -0,0 +1,150 @@
+// SPDX-FileCopyrightText: 2026 (c) Gary "BLumia" Wang <opensource@blumia.net>
+//
+// SPDX-License-Identifier: MIT
+
+use crate::{
+    pe::{pe_offset, read_u16_le},
+    res::{ResId, Resource},
+};
+
+/// Offset of the resource table offset within the NE header.
+const NE_RESOURCE_TABLE: usize = 0x24;
+const TYPEINFO_SIZE: usize = 8;
+const NAMEINFO_SIZE: usize = 12;
+
+/// Returns true for 16-bit "NE" executables, including `.icl` icon libraries.
+pub fn is_ne(bytes: &[u8]) -> bool {
+    pe_offset(bytes)
+        .and_then(|off| bytes.get(off..off + 2))
+        .is_some_and(|sig| sig == b"NE")
+}
+
+/// Reads every resource from the NE resource table. NE resources carry no
+/// language, so `language` is always `None`.
+pub fn read_ne_resources(bytes: &[u8]) -> Result<Vec<Resource>, String> {
+    let ne_offset = pe_offset(bytes).ok_or_else(|| "not a NE file".to_string())?;
+    let table_offset = read_u16_le(bytes, ne_offset + NE_RESOURCE_TABLE)? as usize;
+    // A zero offset means the module has no resources at all.
+    if table_offset == 0 {
+        return Ok(Vec::new());
+    }
+    let table = ne_offset + table_offset;
+    let align_shift = read_u16_le(bytes, table)? as u32;
+    if align_shift > 16 {
+        return Err("invalid resource alignment".to_string());
+    }
+
+    let mut out = Vec::new();
+    let mut pos = table + 2;
+    loop {
+        let type_field = read_u16_le(bytes, pos)?;
+        if type_field == 0 {
+            break;
+        }
+        let count = read_u16_le(bytes, pos + 2)? as usize;
+        let type_id = read_id(bytes, table, type_field)?;
+        pos += TYPEINFO_SIZE;
+
+        for _ in 0..count {
+            let offset = (read_u16_le(bytes, pos)? as usize) << align_shift;
+            let length = (read_u16_le(bytes, pos + 2)? as usize) << align_shift;
+            let name = read_id(bytes, table, read_u16_le(bytes, pos + 6)?)?;
+            pos += NAMEINFO_SIZE;
+
+            let data = bytes
+                .get(offset..offset + length)
+                .ok_or_else(|| "premature end".to_string())?
+                .to_vec();
+            out.push(Resource {
+                type_id: type_id.clone(),
+                name,
+                language: None,
+                offset,
+                data,
+            });
+        }
+    }
+    Ok(out)
+}
+
+/// Decodes a type or name field: an ordinal when the high bit is set, else
+/// the offset of a length-prefixed string relative to the resource table.
+fn read_id(bytes: &[u8], table: usize, field: u16) -> Result<ResId, String> {
+    if field & 0x8000 != 0 {
+        return Ok(ResId::Id(field & 0x7fff));
+    }
+    let start = table + field as usize;
+    let len = *bytes
+        .get(start)
+        .ok_or_else(|| "premature end".to_string())? as usize;
+    let name = bytes
+        .get(start + 1..start + 1 + len)
+        .ok_or_else(|| "premature end".to_string())?;
+    Ok(ResId::Name(String::from_utf8_lossy(name).into_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::res::{RT_GROUP_ICON, RT_ICON, format_resource_line, group_to_ico};
+
+    #[test]
+    fn ne_resources_rebuild_icon_group() {
+        let icon = ico::IconDirEntry::encode_as_bmp(&ico::IconImage::from_rgba_data(
+            1,
+            1,
+            vec![1, 2, 3, 255],
+        ))
+        .unwrap()
+        .data()
+        .to_vec();
+
+        let mut group = vec![0, 0, 1, 0, 1, 0, 1, 1, 0, 0, 1, 0, 32, 0];
+        group.extend((icon.len() as u32).to_le_bytes());
+        group.extend(1u16.to_le_bytes());
+
+        // Resource table at 0x80 with a 16-byte alignment shift; data from 0x100.
+        let mut bytes = vec![0u8; 0x100];
+        bytes[0..2].copy_from_slice(b"MZ");
+        bytes[0x3c] = 0x40;
+        bytes[0x40..0x42].copy_from_slice(b"NE");
+        bytes[0x64..0x66].copy_from_slice(&0x40u16.to_le_bytes());
+
+        let mut table = 4u16.to_le_bytes().to_vec();
+        let mut data_offset = 0x100usize;
+        for (type_id, name, data) in [
+            (0x8000 | RT_ICON, 0x8001u16, &icon),
+            (0x8000 | RT_GROUP_ICON, 44, &group),
+        ] {
+            table.extend(type_id.to_le_bytes());
+            table.extend(1u16.to_le_bytes());
+            table.extend([0; 4]);
+            let units = data.len().div_ceil(16);
+            for v in [(data_offset >> 4) as u16, units as u16, 0x30, name, 0, 0] {
+                table.extend(v.to_le_bytes());
+            }
+            data_offset += units * 16;
+        }
+        table.extend([0, 0]);
+        table.extend([3, b'A', b'P', b'P', 0]);
+        bytes[0x80..0x80 + table.len()].copy_from_slice(&table);
+        for data in [&icon, &group] {
+            let mut padded = data.clone();
+            padded.resize(data.len().div_ceil(16) * 16, 0);
+            bytes.extend(padded);
+        }
+
+        assert!(is_ne(&bytes));
+        let resources = read_ne_resources(&bytes).unwrap();
+        assert_eq!(
+            format_resource_line(&resources[1]),
+            format!(
+                "--type=14 --name='APP' [type=group_icon offset=0x{:x} size=32]",
+                0x100 + icon.len().div_ceil(16) * 16
+            )
+        );
+        let ico_file = group_to_ico(&resources[1], &resources).unwrap();
+        let dir = ico::IconDir::read(std::io::Cursor::new(ico_file)).unwrap();
+        assert_eq!(dir.entries()[0].data(), icon.as_slice());
+    }
+}
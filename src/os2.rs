@@ -0,0 +1,345 @@
+// SPDX-FileCopyrightText: 2026 (c) Gary "BLumia" Wang <opensource@blumia.net>
+//
+// SPDX-License-Identifier: MIT
+
+use crate::{
+    create::{EncodedImage, build_ico, decode_png_rgba, is_png_bytes},
+    dib::{decode_dib, decode_split_bitmap, encode_dib, row_stride},
+    pe::{read_u16_le, read_u32_le},
+};
+
+/// Size of BITMAPARRAYFILEHEADER without its embedded BITMAPFILEHEADER.
+const ARRAY_HEADER_SIZE: usize = 14;
+/// Size of BITMAPFILEHEADER without its embedded bitmap header.
+const FILE_HEADER_SIZE: usize = 14;
+const CORE_HEADER_SIZE: usize = 12;
+
+const KINDS: [&[u8; 2]; 4] = [b"IC", b"CI", b"PT", b"CP"];
+
+/// One image of an OS/2 icon or pointer file.
+struct Os2Image {
+    is_pointer: bool,
+    width: u32,
+    height: u32,
+    bit_depth: u32,
+    /// Hotspot measured from the top-left corner.
+    hotspot_x: i32,
+    hotspot_y: i32,
+    /// Top-down RGBA pixels; pixels set in the AND mask are transparent.
+    rgba: Vec<u8>,
+}
+
+/// Returns true for OS/2 icon and pointer files: a bitmap array (`BA`) or a
+/// single `IC`, `CI`, `PT` or `CP` image.
+pub fn is_os2(bytes: &[u8]) -> bool {
+    let Some(tag) = bytes.get(0..2) else {
+        return false;
+    };
+    if tag == b"BA" {
+        return bytes
+            .get(ARRAY_HEADER_SIZE..ARRAY_HEADER_SIZE + 2)
+            .is_some_and(|t| KINDS.iter().any(|k| t == *k));
+    }
+    bytes.len() >= FILE_HEADER_SIZE + CORE_HEADER_SIZE && KINDS.iter().any(|k| tag == *k)
+}
+
+/// Converts an OS/2 icon or pointer file into a Windows icon or cursor file
+/// holding the same images at their original bit depths.
+pub fn os2_to_ico(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let images = read_os2(bytes)?;
+    let is_pointer = images.first().is_some_and(|i| i.is_pointer);
+
+    let mut encoded = Vec::with_capacity(images.len());
+    for image in images {
+        let bit_depth = if matches!(image.bit_depth, 1 | 4 | 8 | 24) {
+            image.bit_depth
+        } else {
+            32
+        };
+        let data = encode_dib(image.width, image.height, &image.rgba, bit_depth, 0)?;
+        encoded.push(EncodedImage::from_data(
+            data,
+            image.hotspot_x,
+            image.hotspot_y,
+        )?);
+    }
+    let container_type = if is_pointer { 2 } else { 1 };
+    Ok(build_ico(container_type, is_pointer, &encoded, true))
+}
+
+fn read_os2(bytes: &[u8]) -> Result<Vec<Os2Image>, String> {
+    let mut images = Vec::new();
+    let mut pos = 0usize;
+    loop {
+        if bytes.get(pos..pos + 2) != Some(b"BA") {
+            // A bare image without an array around it.
+            images.push(read_image(bytes, pos)?);
+            break;
+        }
+        images.push(read_image(bytes, pos + ARRAY_HEADER_SIZE)?);
+        let next = read_u32_le(bytes, pos + 6)? as usize;
+        if next == 0 {
+            break;
+        }
+        // Arrays only ever link forward; anything else would loop forever.
+        if next <= pos {
+            return Err("invalid bitmap array".to_string());
+        }
+        pos = next;
+    }
+    Ok(images)
+}
+
+/// Reads the image whose BITMAPFILEHEADER starts at `offset`. Every image has
+/// a monochrome bitmap of twice the image height holding the XOR mask in its
+/// lower half and the AND mask in its upper half; color images follow it with
+/// a second header for the color bitmap.
+fn read_image(bytes: &[u8], offset: usize) -> Result<Os2Image, String> {
+    let kind = bytes
+        .get(offset..offset + 2)
+        .ok_or_else(|| "premature end".to_string())?;
+    let (is_pointer, is_color) = match kind {
+        b"IC" => (false, false),
+        b"CI" => (false, true),
+        b"PT" => (true, false),
+        b"CP" => (true, true),
+        _ => return Err("not an OS/2 icon or pointer file".to_string()),
+    };
+    let hotspot_x = read_u16_le(bytes, offset + 6)? as i16 as i32;
+    let hotspot_y = read_u16_le(bytes, offset + 8)? as i16 as i32;
+
+    let mask_header = bytes
+        .get(offset + FILE_HEADER_SIZE..)
+        .ok_or_else(|| "premature end".to_string())?;
+    let mask_offset = read_u32_le(bytes, offset + 10)? as usize;
+    let mask = decode_split_bitmap(mask_header, bytes, mask_offset)?;
+    if mask.bit_depth != 1 || mask.height % 2 != 0 {
+        return Err("invalid icon mask".to_string());
+    }
+    let width = mask.width;
+    let height = mask.height / 2;
+
+    let color = if is_color {
+        let color_offset = offset + FILE_HEADER_SIZE + mask.header_len;
+        if bytes.get(color_offset..color_offset + 2) != Some(kind) {
+            return Err("missing color bitmap".to_string());
+        }
+        let bits_offset = read_u32_le(bytes, color_offset + 10)? as usize;
+        let color_header = bytes
+            .get(color_offset + FILE_HEADER_SIZE..)
+            .ok_or_else(|| "premature end".to_string())?;
+        let color = decode_split_bitmap(color_header, bytes, bits_offset)?;
+        if color.width != width || color.height != height {
+            return Err("color bitmap and mask differ in size".to_string());
+        }
+        Some(color)
+    } else {
+        None
+    };
+
+    let stride = row_stride(width, 1);
+    let bits = stride
+        .checked_mul(height as usize * 2)
+        .and_then(|len| bytes.get(mask_offset..mask_offset.checked_add(len)?))
+        .ok_or_else(|| "premature end".to_string())?;
+    // Rows are stored bottom-up, so the XOR half comes first.
+    let bit = |row: usize, x: usize| bits[row * stride + x / 8] >> (7 - x % 8) & 1 != 0;
+
+    let (w, h) = (width as usize, height as usize);
+    let mut rgba = vec![0u8; w * h * 4];
+    for y in 0..h {
+        for x in 0..w {
+            let xor = bit(h - 1 - y, x);
+            let and = bit(2 * h - 1 - y, x);
+            let px = &mut rgba[(y * w + x) * 4..(y * w + x) * 4 + 4];
+            match &color {
+                Some(color) => {
+                    px.copy_from_slice(&color.rgba[(y * w + x) * 4..(y * w + x) * 4 + 4])
+                }
+                None if xor => px.copy_from_slice(&[255, 255, 255, 255]),
+                None => px.copy_from_slice(&[0, 0, 0, 255]),
+            }
+            if and {
+                px[3] = 0;
+            }
+        }
+    }
+
+    // OS/2 measures the hotspot from the bottom-left corner.
+    let hotspot_y = (height as i32 - 1 - hotspot_y).max(0);
+    Ok(Os2Image {
+        is_pointer,
+        width,
+        height,
+        bit_depth: color.as_ref().map_or(1, |c| c.bit_depth),
+        hotspot_x,
+        hotspot_y,
+        rgba,
+    })
+}
+
+/// Writes `images` as an OS/2 1.x bitmap array of color icons (`CI`) or
+/// pointers (`CP`), which both OS/2 1.x and 2.x can read.
+///
+/// OS/2 bitmaps have no alpha channel, so 32-bit images are stored as 24-bit
+/// ones and pixels at or below `alpha_threshold` become transparent.
+pub(crate) fn build_os2(
+    images: &[EncodedImage],
+    is_pointer: bool,
+    alpha_threshold: i32,
+) -> Result<Vec<u8>, String> {
+    let kind = if is_pointer { b"CP" } else { b"CI" };
+
+    // Convert each image to an icon DIB first, then split it into the pieces
+    // the array stores apart.
+    let mut dibs = Vec::with_capacity(images.len());
+    for image in images {
+        let (width, height, rgba) = if is_png_bytes(&image.data) {
+            decode_png_rgba(&image.data)?
+        } else {
+            let dib = decode_dib(&image.data)?;
+            (dib.width, dib.height, dib.rgba)
+        };
+        if width > u16::MAX as u32 || height * 2 > u16::MAX as u32 {
+            return Err("image is too large for an OS/2 icon".to_string());
+        }
+        let bit_depth = if matches!(image.bit_depth, 1 | 4 | 8) {
+            image.bit_depth
+        } else {
+            24
+        };
+        let dib = encode_dib(width, height, &rgba, bit_depth, alpha_threshold)?;
+        dibs.push((width, height, bit_depth, dib));
+    }
+
+    let entry_len = |bit_depth: u32| {
+        let palette = if bit_depth <= 8 {
+            1usize << bit_depth
+        } else {
+            0
+        };
+        ARRAY_HEADER_SIZE + 2 * (FILE_HEADER_SIZE + CORE_HEADER_SIZE) + (2 + palette) * 3
+    };
+    let headers_len: usize = dibs.iter().map(|(_, _, bpp, _)| entry_len(*bpp)).sum();
+
+    let mut out = Vec::new();
+    let mut bits = Vec::new();
+    for (i, (image, (width, height, bit_depth, dib))) in images.iter().zip(&dibs).enumerate() {
+        let palette_len = if *bit_depth <= 8 {
+            1usize << bit_depth
+        } else {
+            0
+        };
+        let palette = &dib[40..40 + palette_len * 4];
+        let xor_len = row_stride(*width, *bit_depth) * *height as usize;
+        let color_bits = &dib[40 + palette_len * 4..40 + palette_len * 4 + xor_len];
+        let and_bits = &dib[40 + palette_len * 4 + xor_len..];
+
+        let next = if i + 1 < images.len() {
+            out.len() + entry_len(*bit_depth)
+        } else {
+            0
+        };
+        let (hotspot_x, hotspot_y) = if is_pointer {
+            let y = (*height as i32 - 1 - image.hotspot_y as i32).max(0);
+            (image.hotspot_x, y as u16)
+        } else {
+            (0, 0)
+        };
+        let mask_offset = headers_len + bits.len();
+        let color_offset = mask_offset + and_bits.len() * 2;
+
+        out.extend_from_slice(b"BA");
+        out.extend_from_slice(&40u32.to_le_bytes());
+        out.extend_from_slice(&(next as u32).to_le_bytes());
+        out.extend_from_slice(&[0; 4]);
+
+        for (is_mask, offset, bmp_height, bmp_depth) in [
+            (true, mask_offset, height * 2, 1),
+            (false, color_offset, *height, *bit_depth),
+        ] {
+            out.extend_from_slice(kind);
+            out.extend_from_slice(&26u32.to_le_bytes());
+            out.extend_from_slice(&hotspot_x.to_le_bytes());
+            out.extend_from_slice(&hotspot_y.to_le_bytes());
+            out.extend_from_slice(&(offset as u32).to_le_bytes());
+            out.extend_from_slice(&(CORE_HEADER_SIZE as u32).to_le_bytes());
+            out.extend_from_slice(&(*width as u16).to_le_bytes());
+            out.extend_from_slice(&(bmp_height as u16).to_le_bytes());
+            out.extend_from_slice(&1u16.to_le_bytes());
+            out.extend_from_slice(&(bmp_depth as u16).to_le_bytes());
+            if is_mask {
+                out.extend_from_slice(&[0, 0, 0, 255, 255, 255]);
+            } else {
+                for quad in palette.chunks_exact(4) {
+                    out.extend_from_slice(&quad[..3]);
+                }
+            }
+        }
+
+        // An all-zero XOR mask leaves the color bitmap in charge.
+        bits.resize(bits.len() + and_bits.len(), 0);
+        bits.extend_from_slice(and_bits);
+        bits.extend_from_slice(color_bits);
+    }
+
+    out.extend_from_slice(&bits);
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::parse_dib_info;
+
+    #[test]
+    fn os2_pointer_round_trips_through_ico() {
+        // 2x2 pointer: red, green / transparent, blue.
+        let rgba = vec![
+            255, 0, 0, 255, 0, 255, 0, 255, //
+            0, 0, 0, 0, 0, 0, 255, 255,
+        ];
+        let dib = encode_dib(2, 2, &rgba, 24, 0).unwrap();
+        let image = EncodedImage::from_data(dib, 1, 0).unwrap();
+        let bytes = build_os2(&[image], true, 0).unwrap();
+        assert!(bytes.starts_with(b"BA"));
+        assert!(is_os2(&bytes));
+
+        let ico = os2_to_ico(&bytes).unwrap();
+        let dir = ico::IconDir::read(std::io::Cursor::new(ico)).unwrap();
+        assert_eq!(dir.resource_type(), ico::ResourceType::Cursor);
+        let entry = &dir.entries()[0];
+        assert_eq!(entry.cursor_hotspot(), Some((1, 0)));
+        assert_eq!(parse_dib_info(entry.data()).unwrap().2, 24);
+        assert_eq!(entry.decode().unwrap().rgba_data(), rgba.as_slice());
+    }
+
+    #[test]
+    fn reads_monochrome_os2_icon() {
+        // A bare 1x1 "IC" image with a 2.x header cut short after the bit
+        // count: black XOR half first, then a clear AND half.
+        let mut bytes = b"IC".to_vec();
+        bytes.extend(30u32.to_le_bytes());
+        bytes.extend([0; 4]);
+        bytes.extend(38u32.to_le_bytes());
+        bytes.extend(16u32.to_le_bytes());
+        bytes.extend(1u32.to_le_bytes());
+        bytes.extend(2u32.to_le_bytes());
+        bytes.extend(1u16.to_le_bytes());
+        bytes.extend(1u16.to_le_bytes());
+        bytes.extend([0, 0, 0, 0, 255, 255, 255, 0]);
+        bytes.extend([0x80, 0, 0, 0, 0, 0, 0, 0]);
+
+        assert!(is_os2(&bytes));
+        let images = read_os2(&bytes).unwrap();
+        assert_eq!(images.len(), 1);
+        assert!(!images[0].is_pointer);
+        assert_eq!(images[0].bit_depth, 1);
+        assert_eq!(images[0].rgba, vec![255, 255, 255, 255]);
+
+        // The same header claiming a huge mask must fail, not allocate it.
+        bytes[18..22].copy_from_slice(&0x7fff_ffffu32.to_le_bytes());
+        bytes[22..26].copy_from_slice(&0x7fff_fffeu32.to_le_bytes());
+        assert_eq!(read_os2(&bytes).err().unwrap(), "premature end");
+    }
+}
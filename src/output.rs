@@ -0,0 +1,204 @@
+// SPDX-FileCopyrightText: 2026 (c) Gary "BLumia" Wang <opensource@blumia.net>
+//
+// SPDX-License-Identifier: MIT
+
+use std::{
+    collections::HashSet,
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+
+/// Writes `data` to `path` through a temporary file in the same directory that
+/// is renamed into place once complete, so a failed write never leaves a
+/// truncated file behind. The temporary file takes over the permissions and,
+/// where possible, the owner of the file it replaces.
+///
+/// Anything but a missing path or a regular file, such as a device or a
+/// symlink, is written directly so that it keeps being what it is.
+///
+/// With `no_clobber`, an existing `path` is left alone and the error kind is
+/// `AlreadyExists`.
+pub fn write_file(path: &str, data: &[u8], no_clobber: bool) -> io::Result<()> {
+    let path = Path::new(path);
+    let existing = match fs::symlink_metadata(path) {
+        Ok(meta) => Some(meta),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => None,
+        Err(e) => return Err(e),
+    };
+    if let Some(meta) = &existing {
+        if no_clobber {
+            return Err(io::ErrorKind::AlreadyExists.into());
+        }
+        if !meta.file_type().is_file() {
+            return fs::write(path, data);
+        }
+    }
+
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| io::Error::from(io::ErrorKind::InvalidInput))?
+        .to_string_lossy();
+    let dir = path.parent().filter(|p| !p.as_os_str().is_empty());
+
+    let (tmp_path, mut file) = create_temp(dir, &file_name)?;
+    if let Some(meta) = &existing {
+        if file
+            .set_permissions(meta.permissions())
+            .and_then(|_| copy_owner(&file, meta))
+            .is_err()
+        {
+            // Replacing the file would change who may use it.
+            drop(file);
+            let _ = fs::remove_file(&tmp_path);
+            return fs::write(path, data);
+        }
+    }
+    let written = file.write_all(data).and_then(|_| file.sync_all());
+    drop(file);
+    let result = written.and_then(|_| {
+        if no_clobber {
+            link_new(&tmp_path, path)
+        } else {
+            fs::rename(&tmp_path, path)
+        }
+    });
+    // After a rename there is nothing left to remove.
+    let _ = fs::remove_file(&tmp_path);
+    result
+}
+
+#[cfg(unix)]
+fn copy_owner(file: &fs::File, meta: &fs::Metadata) -> io::Result<()> {
+    use std::os::unix::fs::{MetadataExt, fchown};
+
+    let current = file.metadata()?;
+    if (current.uid(), current.gid()) == (meta.uid(), meta.gid()) {
+        return Ok(());
+    }
+    fchown(file, Some(meta.uid()), Some(meta.gid()))
+}
+
+#[cfg(not(unix))]
+fn copy_owner(_file: &fs::File, _meta: &fs::Metadata) -> io::Result<()> {
+    Ok(())
+}
+
+fn create_temp(dir: Option<&Path>, file_name: &str) -> io::Result<(std::path::PathBuf, fs::File)> {
+    let pid = std::process::id();
+    for n in 0.. {
+        let name = format!(".{file_name}.{pid}-{n}.tmp");
+        let tmp_path = match dir {
+            Some(dir) => dir.join(name),
+            None => name.into(),
+        };
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&tmp_path)
+        {
+            Ok(file) => return Ok((tmp_path, file)),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    unreachable!()
+}
+
+/// Moves `tmp_path` to `path` unless `path` exists. A hard link fails
+/// atomically when the target exists; file systems without hard links fall
+/// back to checking first.
+fn link_new(tmp_path: &Path, path: &Path) -> io::Result<()> {
+    match fs::hard_link(tmp_path, path) {
+        Ok(()) => Ok(()),
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => Err(e),
+        Err(_) if path.exists() => Err(io::ErrorKind::AlreadyExists.into()),
+        Err(_) => fs::rename(tmp_path, path),
+    }
+}
+
+/// Output names handed out during one run. Names that were already used get
+/// a `-2`, `-3`, ... suffix before their extension, so inputs sharing a stem
+/// do not overwrite each other's files.
+#[derive(Default)]
+pub struct OutputNames {
+    used: HashSet<String>,
+}
+
+impl OutputNames {
+    pub fn claim(&mut self, path: &str) -> String {
+        if self.used.insert(path.to_string()) {
+            return path.to_string();
+        }
+
+        let file_start = path.rfind(['/', '\\']).map_or(0, |pos| pos + 1);
+        let (base, ext) = match path[file_start..].rfind('.') {
+            Some(dot) if dot > 0 => path.split_at(file_start + dot),
+            _ => (path, ""),
+        };
+        for n in 2.. {
+            let candidate = format!("{base}-{n}{ext}");
+            if self.used.insert(candidate.clone()) {
+                return candidate;
+            }
+        }
+        unreachable!()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn claim_deduplicates_names_within_a_run() {
+        let mut names = OutputNames::default();
+        assert_eq!(names.claim("out/a_1.png"), "out/a_1.png");
+        assert_eq!(names.claim("out/a_1.png"), "out/a_1-2.png");
+        assert_eq!(names.claim("out/a_1.png"), "out/a_1-3.png");
+        assert_eq!(names.claim("v1.0/icon"), "v1.0/icon");
+        assert_eq!(names.claim("v1.0/icon"), "v1.0/icon-2");
+    }
+
+    #[test]
+    fn write_file_replaces_or_keeps_existing_files() {
+        let dir = std::env::temp_dir().join(format!("icoutils-rs-output-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("out.bin");
+        let path_str = path.to_str().unwrap();
+
+        write_file(path_str, b"first", false).unwrap();
+        write_file(path_str, b"second", false).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"second");
+
+        let err = write_file(path_str, b"third", true).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(fs::read(&path).unwrap(), b"second");
+        // No temporary files are left behind either way.
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{PermissionsExt, symlink};
+
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o751)).unwrap();
+            write_file(path_str, b"fourth", false).unwrap();
+            let mode = fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o751);
+
+            // Symlinks are written through rather than replaced.
+            let link = dir.join("link.bin");
+            symlink(&path, &link).unwrap();
+            write_file(link.to_str().unwrap(), b"fifth", false).unwrap();
+            assert!(
+                fs::symlink_metadata(&link)
+                    .unwrap()
+                    .file_type()
+                    .is_symlink()
+            );
+            assert_eq!(fs::read(&path).unwrap(), b"fifth");
+        }
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -50,6 +50,9 @@ pub fn parse_dib_info(data: &[u8]) -> Result<(u32, u32, u32, u32), String> {
         return Err("premature end".to_string());
     }
     let header_size = read_le_u32(data, 0)? as usize;
+    if header_size == 12 {
+        return parse_core_header_info(data);
+    }
     if header_size < 40 {
         return Err("bitmap header is too short".to_string());
     }
@@ -65,8 +68,10 @@ pub fn parse_dib_info(data: &[u8]) -> Result<(u32, u32, u32, u32), String> {
     let clr_used = read_le_u32(data, 32)?;
     let clr_important = read_le_u32(data, 36)?;
 
-    if compression != 0 {
-        return Err("compressed image data not supported".to_string());
+    // BI_RGB, BI_RLE8, BI_RLE4, BI_BITFIELDS and BI_ALPHABITFIELDS.
+    match (compression, bit_count) {
+        (0, _) | (1, 8) | (2, 4) | (3 | 6, 16 | 32) => {}
+        _ => return Err("unsupported bitmap compression".to_string()),
     }
     if planes != 1 {
         return Err("planes field in bitmap should be one".to_string());
@@ -79,7 +84,7 @@ pub fn parse_dib_info(data: &[u8]) -> Result<(u32, u32, u32, u32), String> {
     }
 
     let height_abs = height.unsigned_abs();
-    let image_height = (height_abs / 2) as u32;
+    let image_height = height_abs / 2;
     let palette_count = if clr_used != 0 || bit_count < 24 {
         if clr_used != 0 {
             clr_used
@@ -96,6 +101,29 @@ pub fn parse_dib_info(data: &[u8]) -> Result<(u32, u32, u32, u32), String> {
     Ok((width as u32, image_height, bit_count as u32, palette_count))
 }
 
+/// Parses an OS/2 BITMAPCOREHEADER, which has 16-bit dimensions and no
+/// compression or palette size fields.
+fn parse_core_header_info(data: &[u8]) -> Result<(u32, u32, u32, u32), String> {
+    let width = read_le_u16(data, 4)? as u32;
+    let height = read_le_u16(data, 6)? as u32;
+    let planes = read_le_u16(data, 8)?;
+    let bit_count = read_le_u16(data, 10)? as u32;
+
+    if planes != 1 {
+        return Err("planes field in bitmap should be one".to_string());
+    }
+    if width == 0 {
+        return Err("invalid bitmap width".to_string());
+    }
+    let palette_count = if bit_count < 24 {
+        1u32.checked_shl(bit_count)
+            .ok_or_else(|| "palette too large".to_string())?
+    } else {
+        0
+    };
+    Ok((width, height / 2, bit_count, palette_count))
+}
+
 fn read_be_u32(data: &[u8], offset: usize) -> Result<u32, String> {
     let end = offset
         .checked_add(4)
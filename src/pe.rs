@@ -0,0 +1,599 @@
+// SPDX-FileCopyrightText: 2026 (c) Gary "BLumia" Wang <opensource@blumia.net>
+//
+// SPDX-License-Identifier: MIT
+
+use std::collections::HashSet;
+
+use crate::{
+    res::{ResId, Resource},
+    rsrc::{align, build_resource_section},
+};
+
+const IMAGE_DIRECTORY_ENTRY_RESOURCE: usize = 2;
+/// The certificate table, whose "RVA" is really a file offset.
+const IMAGE_DIRECTORY_ENTRY_SECURITY: usize = 4;
+const IMAGE_SCN_CNT_INITIALIZED_DATA: u32 = 0x0000_0040;
+const IMAGE_SCN_MEM_READ: u32 = 0x4000_0000;
+// Optional header fields at the same offsets in PE32 and PE32+.
+const OPT_SECTION_ALIGNMENT: usize = 32;
+const OPT_FILE_ALIGNMENT: usize = 36;
+const OPT_SIZE_OF_IMAGE: usize = 56;
+const OPT_SIZE_OF_HEADERS: usize = 60;
+const OPT_CHECKSUM: usize = 64;
+const PE32_MAGIC: u16 = 0x10b;
+const PE32_PLUS_MAGIC: u16 = 0x20b;
+const SECTION_HEADER_SIZE: usize = 40;
+/// Resource trees are type/name/language; anything deeper is malformed.
+const MAX_RESOURCE_DEPTH: usize = 3;
+
+pub struct Section {
+    pub name: [u8; 8],
+    pub virtual_size: u32,
+    pub virtual_address: u32,
+    pub size_of_raw_data: u32,
+    pub pointer_to_raw_data: u32,
+}
+
+pub struct PeHeaders {
+    /// Offset of the "PE\0\0" signature.
+    pub pe_offset: usize,
+    /// Offset of the optional header.
+    pub optional_offset: usize,
+    pub is_pe32_plus: bool,
+    pub number_of_rva_and_sizes: u32,
+    /// Offset of the first section header.
+    pub section_table_offset: usize,
+    pub sections: Vec<Section>,
+}
+
+impl PeHeaders {
+    /// Offset of the data directory entry `index` within the file.
+    pub fn data_directory_offset(&self, index: usize) -> usize {
+        let base = if self.is_pe32_plus { 112 } else { 96 };
+        self.optional_offset + base + index * 8
+    }
+
+    pub fn rva_to_offset(&self, rva: u32) -> Option<usize> {
+        self.sections.iter().find_map(|s| {
+            let size = s.virtual_size.max(s.size_of_raw_data);
+            if rva >= s.virtual_address && rva - s.virtual_address < size {
+                let delta = rva - s.virtual_address;
+                if delta < s.size_of_raw_data {
+                    return Some(s.pointer_to_raw_data as usize + delta as usize);
+                }
+            }
+            None
+        })
+    }
+}
+
+pub fn pe_offset(bytes: &[u8]) -> Option<usize> {
+    if bytes.len() < 0x40 || &bytes[0..2] != b"MZ" {
+        return None;
+    }
+    let offset = read_u32_le(bytes, 0x3c).ok()? as usize;
+    Some(offset)
+}
+
+pub fn is_pe(bytes: &[u8]) -> bool {
+    pe_offset(bytes)
+        .and_then(|off| bytes.get(off..off + 4))
+        .is_some_and(|sig| sig == b"PE\0\0")
+}
+
+pub fn parse_headers(bytes: &[u8]) -> Result<PeHeaders, String> {
+    let pe_offset = pe_offset(bytes).ok_or_else(|| "not a PE file".to_string())?;
+    if bytes.get(pe_offset..pe_offset + 4) != Some(b"PE\0\0".as_slice()) {
+        return Err("not a PE file".to_string());
+    }
+
+    let coff = pe_offset + 4;
+    let number_of_sections = read_u16_le(bytes, coff + 2)? as usize;
+    let size_of_optional_header = read_u16_le(bytes, coff + 16)? as usize;
+    let optional_offset = coff + 20;
+
+    let magic = read_u16_le(bytes, optional_offset)?;
+    let is_pe32_plus = match magic {
+        PE32_MAGIC => false,
+        PE32_PLUS_MAGIC => true,
+        _ => return Err("unknown PE optional header magic".to_string()),
+    };
+    let number_of_rva_and_sizes =
+        read_u32_le(bytes, optional_offset + if is_pe32_plus { 108 } else { 92 })?;
+
+    let section_table_offset = optional_offset + size_of_optional_header;
+    let mut sections = Vec::with_capacity(number_of_sections);
+    for i in 0..number_of_sections {
+        let off = section_table_offset + i * SECTION_HEADER_SIZE;
+        let name: [u8; 8] = bytes
+            .get(off..off + 8)
+            .ok_or_else(|| "premature end".to_string())?
+            .try_into()
+            .map_err(|_| "premature end".to_string())?;
+        sections.push(Section {
+            name,
+            virtual_size: read_u32_le(bytes, off + 8)?,
+            virtual_address: read_u32_le(bytes, off + 12)?,
+            size_of_raw_data: read_u32_le(bytes, off + 16)?,
+            pointer_to_raw_data: read_u32_le(bytes, off + 20)?,
+        });
+    }
+
+    Ok(PeHeaders {
+        pe_offset,
+        optional_offset,
+        is_pe32_plus,
+        number_of_rva_and_sizes,
+        section_table_offset,
+        sections,
+    })
+}
+
+/// Returns the RVA and size of the resource directory, if any.
+pub fn resource_directory(bytes: &[u8], headers: &PeHeaders) -> Result<Option<(u32, u32)>, String> {
+    if headers.number_of_rva_and_sizes as usize <= IMAGE_DIRECTORY_ENTRY_RESOURCE {
+        return Ok(None);
+    }
+    let off = headers.data_directory_offset(IMAGE_DIRECTORY_ENTRY_RESOURCE);
+    let rva = read_u32_le(bytes, off)?;
+    let size = read_u32_le(bytes, off + 4)?;
+    if rva == 0 {
+        return Ok(None);
+    }
+    Ok(Some((rva, size)))
+}
+
+pub fn read_pe_resources(bytes: &[u8]) -> Result<Vec<Resource>, String> {
+    let headers = parse_headers(bytes)?;
+    let Some((rva, _)) = resource_directory(bytes, &headers)? else {
+        return Ok(Vec::new());
+    };
+    let base = headers
+        .rva_to_offset(rva)
+        .ok_or_else(|| "resource directory outside of any section".to_string())?;
+
+    let mut out = Vec::new();
+    let mut path = Vec::with_capacity(MAX_RESOURCE_DEPTH);
+    let mut visited = HashSet::new();
+    walk_directory(bytes, &headers, base, 0, &mut path, &mut visited, &mut out)?;
+    Ok(out)
+}
+
+/// A PE image with a rewritten resource section.
+pub struct RewrittenPe {
+    pub bytes: Vec<u8>,
+    /// Whether an Authenticode signature had to be dropped; it would no
+    /// longer match the modified image.
+    pub signature_removed: bool,
+}
+
+/// Replaces the resource tree of a PE image with `resources`.
+///
+/// The existing resource section is rewritten in place when it is the last
+/// section of the image, so that it can grow freely. Otherwise a new `.rsrc`
+/// section is appended and the old one is left unused. SizeOfImage and the
+/// checksum are updated to match.
+pub fn replace_pe_resources(bytes: &[u8], resources: &[Resource]) -> Result<RewrittenPe, String> {
+    let headers = parse_headers(bytes)?;
+    let opt = headers.optional_offset;
+    let section_alignment = read_u32_le(bytes, opt + OPT_SECTION_ALIGNMENT)? as usize;
+    let file_alignment = read_u32_le(bytes, opt + OPT_FILE_ALIGNMENT)? as usize;
+    let size_of_headers = read_u32_le(bytes, opt + OPT_SIZE_OF_HEADERS)? as usize;
+    if section_alignment == 0 || file_alignment == 0 {
+        return Err("invalid section alignment".to_string());
+    }
+    if headers.number_of_rva_and_sizes as usize <= IMAGE_DIRECTORY_ENTRY_RESOURCE {
+        return Err("image has no resource directory entry".to_string());
+    }
+
+    let certificate = if headers.number_of_rva_and_sizes as usize > IMAGE_DIRECTORY_ENTRY_SECURITY {
+        let off = headers.data_directory_offset(IMAGE_DIRECTORY_ENTRY_SECURITY);
+        let start = read_u32_le(bytes, off)? as usize;
+        let size = read_u32_le(bytes, off + 4)? as usize;
+        (size != 0).then_some(start..start + size)
+    } else {
+        None
+    };
+
+    let raw_end = |s: &Section| s.pointer_to_raw_data as usize + s.size_of_raw_data as usize;
+    let virtual_end =
+        |s: &Section| s.virtual_address as usize + s.virtual_size.max(s.size_of_raw_data) as usize;
+    let last_by_va =
+        (0..headers.sections.len()).max_by_key(|&i| headers.sections[i].virtual_address);
+    let last_by_raw = (0..headers.sections.len()).max_by_key(|&i| raw_end(&headers.sections[i]));
+    let old_rsrc = resource_directory(bytes, &headers)?.and_then(|(rva, _)| {
+        headers
+            .sections
+            .iter()
+            .position(|s| s.virtual_address == rva)
+    });
+    let reuse = old_rsrc.filter(|&i| Some(i) == last_by_va && Some(i) == last_by_raw);
+
+    // Everything behind the sections is kept, except for the signature.
+    let sections_end = headers
+        .sections
+        .iter()
+        .enumerate()
+        .filter(|&(i, _)| Some(i) != reuse)
+        .map(|(_, s)| raw_end(s))
+        .chain([size_of_headers])
+        .max()
+        .unwrap_or(0)
+        .min(bytes.len());
+    let overlay_start = match reuse {
+        Some(i) => raw_end(&headers.sections[i]).min(bytes.len()),
+        None => sections_end,
+    };
+    let mut overlay = bytes[overlay_start..].to_vec();
+    if let Some(cert) = &certificate {
+        if cert.start >= overlay_start && cert.end <= bytes.len() {
+            overlay.drain(cert.start - overlay_start..cert.end - overlay_start);
+        }
+    }
+
+    let (section_index, virtual_address, pointer_to_raw_data) = match reuse {
+        Some(i) => {
+            let s = &headers.sections[i];
+            (i, s.virtual_address, s.pointer_to_raw_data as usize)
+        }
+        None => {
+            let index = headers.sections.len();
+            let header_end = headers.section_table_offset + (index + 1) * SECTION_HEADER_SIZE;
+            let first_raw = headers
+                .sections
+                .iter()
+                .filter(|s| s.size_of_raw_data != 0)
+                .map(|s| s.pointer_to_raw_data as usize)
+                .min()
+                .unwrap_or(usize::MAX);
+            if header_end > size_of_headers.min(first_raw) {
+                return Err("no room for another section header".to_string());
+            }
+            let va_end = headers.sections.iter().map(virtual_end).max().unwrap_or(0);
+            (
+                index,
+                align(va_end, section_alignment) as u32,
+                align(sections_end, file_alignment),
+            )
+        }
+    };
+
+    let section = build_resource_section(resources, virtual_address).data;
+    let virtual_size = section.len();
+    let raw_size = align(virtual_size, file_alignment);
+
+    let keep = if reuse.is_some() {
+        pointer_to_raw_data.min(bytes.len())
+    } else {
+        sections_end
+    };
+    let mut out = bytes[..keep].to_vec();
+    out.resize(pointer_to_raw_data, 0);
+    out.extend_from_slice(&section);
+    out.resize(pointer_to_raw_data + raw_size, 0);
+    out.extend_from_slice(&overlay);
+
+    let header = headers.section_table_offset + section_index * SECTION_HEADER_SIZE;
+    if reuse.is_none() {
+        out[header..header + SECTION_HEADER_SIZE].fill(0);
+        out[header..header + 8].copy_from_slice(b".rsrc\0\0\0");
+        put_u32(
+            &mut out,
+            header + 36,
+            IMAGE_SCN_CNT_INITIALIZED_DATA | IMAGE_SCN_MEM_READ,
+        );
+        let count = headers.pe_offset + 6;
+        put_u16(&mut out, count, section_index as u16 + 1);
+    }
+    put_u32(&mut out, header + 8, virtual_size as u32);
+    put_u32(&mut out, header + 12, virtual_address);
+    put_u32(&mut out, header + 16, raw_size as u32);
+    put_u32(&mut out, header + 20, pointer_to_raw_data as u32);
+
+    let dir = headers.data_directory_offset(IMAGE_DIRECTORY_ENTRY_RESOURCE);
+    put_u32(&mut out, dir, virtual_address);
+    put_u32(&mut out, dir + 4, virtual_size as u32);
+    if certificate.is_some() {
+        let dir = headers.data_directory_offset(IMAGE_DIRECTORY_ENTRY_SECURITY);
+        put_u32(&mut out, dir, 0);
+        put_u32(&mut out, dir + 4, 0);
+    }
+
+    let image_end = headers
+        .sections
+        .iter()
+        .enumerate()
+        .filter(|&(i, _)| i != section_index)
+        .map(|(_, s)| virtual_end(s))
+        .chain([virtual_address as usize + virtual_size])
+        .max()
+        .unwrap_or(0);
+    put_u32(
+        &mut out,
+        opt + OPT_SIZE_OF_IMAGE,
+        align(image_end, section_alignment) as u32,
+    );
+    let checksum = pe_checksum(&out, opt + OPT_CHECKSUM);
+    put_u32(&mut out, opt + OPT_CHECKSUM, checksum);
+
+    Ok(RewrittenPe {
+        bytes: out,
+        signature_removed: certificate.is_some(),
+    })
+}
+
+/// Computes the image checksum the way `CheckSumMappedFile` does, skipping
+/// the checksum field itself.
+pub fn pe_checksum(bytes: &[u8], checksum_offset: usize) -> u32 {
+    let mut sum: u64 = 0;
+    for (i, word) in bytes.chunks(2).enumerate() {
+        let offset = i * 2;
+        if offset == checksum_offset || offset == checksum_offset + 2 {
+            continue;
+        }
+        sum += u16::from_le_bytes([word[0], *word.get(1).unwrap_or(&0)]) as u64;
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    sum = (sum & 0xffff) + (sum >> 16);
+    sum as u32 + bytes.len() as u32
+}
+
+fn walk_directory(
+    bytes: &[u8],
+    headers: &PeHeaders,
+    base: usize,
+    dir_offset: usize,
+    path: &mut Vec<ResId>,
+    visited: &mut HashSet<usize>,
+    out: &mut Vec<Resource>,
+) -> Result<(), String> {
+    // Each directory belongs to exactly one parent; entries pointing back at
+    // one already walked would otherwise multiply the tree.
+    if !visited.insert(dir_offset) {
+        return Err("resource directory is referenced twice".to_string());
+    }
+    let dir = base + dir_offset;
+    let named = read_u16_le(bytes, dir + 12)? as usize;
+    let ids = read_u16_le(bytes, dir + 14)? as usize;
+
+    for i in 0..named + ids {
+        let entry = dir + 16 + i * 8;
+        let name_field = read_u32_le(bytes, entry)?;
+        let data_field = read_u32_le(bytes, entry + 4)?;
+
+        let id = if name_field & 0x8000_0000 != 0 {
+            ResId::Name(read_dir_string(
+                bytes,
+                base + (name_field & 0x7fff_ffff) as usize,
+            )?)
+        } else {
+            ResId::Id(name_field as u16)
+        };
+
+        if data_field & 0x8000_0000 != 0 {
+            if path.len() + 1 >= MAX_RESOURCE_DEPTH {
+                return Err("resource directory is nested too deeply".to_string());
+            }
+            path.push(id);
+            walk_directory(
+                bytes,
+                headers,
+                base,
+                (data_field & 0x7fff_ffff) as usize,
+                path,
+                visited,
+                out,
+            )?;
+            path.pop();
+            continue;
+        }
+
+        let leaf = base + data_field as usize;
+        let data_rva = read_u32_le(bytes, leaf)?;
+        let size = read_u32_le(bytes, leaf + 4)? as usize;
+        let offset = headers
+            .rva_to_offset(data_rva)
+            .ok_or_else(|| "resource data outside of any section".to_string())?;
+        let data = bytes
+            .get(offset..offset + size)
+            .ok_or_else(|| "premature end".to_string())?
+            .to_vec();
+
+        // Leaves normally sit at the language level; tolerate shallower trees.
+        let (type_id, name, language) = match path.as_slice() {
+            [t, n] => (
+                t.clone(),
+                n.clone(),
+                match &id {
+                    ResId::Id(lang) => Some(*lang),
+                    ResId::Name(_) => None,
+                },
+            ),
+            [t] => (t.clone(), id, None),
+            _ => return Err("resource directory is malformed".to_string()),
+        };
+        out.push(Resource {
+            type_id,
+            name,
+            language,
+            offset,
+            data,
+        });
+    }
+    Ok(())
+}
+
+fn read_dir_string(bytes: &[u8], offset: usize) -> Result<String, String> {
+    let len = read_u16_le(bytes, offset)? as usize;
+    let units: Vec<u16> = (0..len)
+        .map(|i| read_u16_le(bytes, offset + 2 + i * 2))
+        .collect::<Result<_, _>>()?;
+    Ok(String::from_utf16_lossy(&units))
+}
+
+pub fn read_u16_le(data: &[u8], offset: usize) -> Result<u16, String> {
+    let bytes = data
+        .get(offset..offset + 2)
+        .ok_or_else(|| "premature end".to_string())?;
+    Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+}
+
+pub fn read_u32_le(data: &[u8], offset: usize) -> Result<u32, String> {
+    let bytes = data
+        .get(offset..offset + 4)
+        .ok_or_else(|| "premature end".to_string())?;
+    Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+}
+
+fn put_u16(out: &mut [u8], offset: usize, v: u16) {
+    out[offset..offset + 2].copy_from_slice(&v.to_le_bytes());
+}
+
+fn put_u32(out: &mut [u8], offset: usize, v: u32) {
+    out[offset..offset + 4].copy_from_slice(&v.to_le_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::res::format_resource_line;
+
+    /// Builds a PE32 image with a single `.rsrc` section at RVA 0x1000.
+    pub(crate) fn build_test_pe(rsrc: &[u8]) -> Vec<u8> {
+        let mut out = vec![0u8; 0x200];
+        out[0..2].copy_from_slice(b"MZ");
+        out[0x3c..0x40].copy_from_slice(&0x40u32.to_le_bytes());
+        out[0x40..0x44].copy_from_slice(b"PE\0\0");
+        out[0x44..0x46].copy_from_slice(&0x14cu16.to_le_bytes());
+        out[0x46..0x48].copy_from_slice(&1u16.to_le_bytes());
+        out[0x54..0x56].copy_from_slice(&224u16.to_le_bytes());
+
+        let opt = 0x58;
+        out[opt..opt + 2].copy_from_slice(&PE32_MAGIC.to_le_bytes());
+        out[opt + 32..opt + 36].copy_from_slice(&0x1000u32.to_le_bytes());
+        out[opt + 36..opt + 40].copy_from_slice(&0x200u32.to_le_bytes());
+        out[opt + 60..opt + 64].copy_from_slice(&0x200u32.to_le_bytes());
+        out[opt + 92..opt + 96].copy_from_slice(&16u32.to_le_bytes());
+        out[opt + 112..opt + 116].copy_from_slice(&0x1000u32.to_le_bytes());
+        out[opt + 116..opt + 120].copy_from_slice(&(rsrc.len() as u32).to_le_bytes());
+
+        let sec = opt + 224;
+        out[sec..sec + 5].copy_from_slice(b".rsrc");
+        let raw_size = rsrc.len().div_ceil(0x200) * 0x200;
+        for (i, v) in [rsrc.len() as u32, 0x1000, raw_size as u32, 0x200]
+            .iter()
+            .enumerate()
+        {
+            out[sec + 8 + i * 4..sec + 12 + i * 4].copy_from_slice(&v.to_le_bytes());
+        }
+
+        out.extend_from_slice(rsrc);
+        out.resize(0x200 + raw_size, 0);
+        out
+    }
+
+    fn dir_header(named: u16, ids: u16) -> Vec<u8> {
+        let mut d = vec![0u8; 12];
+        d.extend_from_slice(&named.to_le_bytes());
+        d.extend_from_slice(&ids.to_le_bytes());
+        d
+    }
+
+    fn entry(name: u32, data: u32) -> Vec<u8> {
+        [name.to_le_bytes(), data.to_le_bytes()].concat()
+    }
+
+    #[test]
+    fn pe_rejects_directory_loops() {
+        let mut rsrc = Vec::new();
+        rsrc.extend(dir_header(0, 1));
+        rsrc.extend(entry(14, 0x8000_0000 | 24));
+        rsrc.extend(dir_header(0, 2));
+        rsrc.extend(entry(1, 0x8000_0000 | 24));
+        rsrc.extend(entry(2, 0x8000_0000 | 24));
+
+        let pe = build_test_pe(&rsrc);
+        assert!(read_pe_resources(&pe).is_err());
+    }
+
+    #[test]
+    fn pe_lists_named_resource() {
+        let mut rsrc = Vec::new();
+        rsrc.extend(dir_header(0, 1));
+        rsrc.extend(entry(14, 0x8000_0000 | 24));
+        rsrc.extend(dir_header(1, 0));
+        rsrc.extend(entry(0x8000_0000 | 88, 0x8000_0000 | 48));
+        rsrc.extend(dir_header(0, 1));
+        rsrc.extend(entry(1033, 72));
+        for v in [0x1000u32 + 96, 4, 0, 0] {
+            rsrc.extend(v.to_le_bytes());
+        }
+        rsrc.extend(3u16.to_le_bytes());
+        for c in "APP".encode_utf16() {
+            rsrc.extend(c.to_le_bytes());
+        }
+        rsrc.extend([1, 2, 3, 4]);
+
+        let pe = build_test_pe(&rsrc);
+        assert!(is_pe(&pe));
+        let resources = read_pe_resources(&pe).unwrap();
+        assert_eq!(resources.len(), 1);
+        assert_eq!(resources[0].data, vec![1, 2, 3, 4]);
+        assert_eq!(
+            format_resource_line(&resources[0]),
+            "--type=14 --name='APP' --language=1033 [type=group_icon offset=0x260 size=4]"
+        );
+    }
+
+    #[test]
+    fn pe_set_icon_rewrites_resource_section() {
+        use crate::create::{EncodedImage, build_ico};
+        use crate::res::{RT_GROUP_ICON, group_to_ico, set_icon_group};
+
+        let manifest = Resource {
+            type_id: ResId::Id(24),
+            name: ResId::Id(1),
+            language: Some(1033),
+            offset: 0,
+            data: b"<assembly/>".to_vec(),
+        };
+        let rsrc = build_resource_section(std::slice::from_ref(&manifest), 0x1000).data;
+        let pe = build_test_pe(&rsrc);
+
+        let image = ico::IconImage::from_rgba_data(2, 2, vec![255; 16]);
+        let images: Vec<EncodedImage> = [
+            ico::IconDirEntry::encode_as_bmp(&image).unwrap(),
+            ico::IconDirEntry::encode_as_png(&image).unwrap(),
+        ]
+        .iter()
+        .map(|e| EncodedImage::from_data(e.data().to_vec(), 0, 0).unwrap())
+        .collect();
+        let icon = build_ico(1, false, &images, true);
+
+        let mut resources = read_pe_resources(&pe).unwrap();
+        set_icon_group(&mut resources, &icon, None, None).unwrap();
+        let rewritten = replace_pe_resources(&pe, &resources).unwrap();
+        assert!(!rewritten.signature_removed);
+        let out = rewritten.bytes;
+
+        let resources = read_pe_resources(&out).unwrap();
+        assert_eq!(resources.len(), 4);
+        assert_eq!(resources[3].data, manifest.data);
+        let group = &resources[2];
+        assert_eq!(group.type_id, ResId::Id(RT_GROUP_ICON));
+        assert_eq!((&group.name, group.language), (&ResId::Id(1), Some(1033)));
+        assert_eq!(group_to_ico(group, &resources).unwrap(), icon);
+
+        let headers = parse_headers(&out).unwrap();
+        let opt = headers.optional_offset;
+        let rsrc_size = headers.sections[0].virtual_size as usize;
+        assert_eq!(
+            read_u32_le(&out, opt + OPT_SIZE_OF_IMAGE).unwrap() as usize,
+            align(0x1000 + rsrc_size, 0x1000)
+        );
+        assert_eq!(
+            read_u32_le(&out, opt + OPT_CHECKSUM).unwrap(),
+            pe_checksum(&out, opt + OPT_CHECKSUM)
+        );
+    }
+}
@@ -0,0 +1,163 @@
+// SPDX-FileCopyrightText: 2026 (c) Gary "BLumia" Wang <opensource@blumia.net>
+//
+// SPDX-License-Identifier: MIT
+
+use std::collections::{BTreeMap, HashMap};
+
+/// Builds a palette of at most `max_colors` entries for the given RGB colors.
+///
+/// If the colors already fit, they are returned as-is (sorted). Otherwise the
+/// palette is reduced with median cut, weighted by how often each color is used.
+pub fn build_palette(pixels: impl Iterator<Item = [u8; 3]>, max_colors: usize) -> Vec<[u8; 3]> {
+    let mut histogram: BTreeMap<[u8; 3], u32> = BTreeMap::new();
+    for px in pixels {
+        *histogram.entry(px).or_insert(0) += 1;
+    }
+
+    if histogram.len() <= max_colors {
+        return histogram.into_keys().collect();
+    }
+    if max_colors == 0 {
+        return Vec::new();
+    }
+
+    let mut boxes: Vec<Vec<([u8; 3], u32)>> = vec![histogram.into_iter().collect()];
+    while boxes.len() < max_colors {
+        let Some((split_at, channel)) = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| b.len() > 1)
+            .map(|(i, b)| {
+                let (channel, range) = widest_channel(b);
+                (i, channel, range)
+            })
+            .max_by_key(|&(_, _, range)| range)
+            .map(|(i, channel, _)| (i, channel))
+        else {
+            break;
+        };
+
+        let mut colors = boxes.swap_remove(split_at);
+        colors.sort_by_key(|(c, _)| c[channel]);
+        let total: u64 = colors.iter().map(|&(_, n)| n as u64).sum();
+        let mut acc = 0u64;
+        let mut mid = colors.len() - 1;
+        for (i, &(_, n)) in colors.iter().enumerate() {
+            acc += n as u64;
+            if acc * 2 >= total {
+                mid = i + 1;
+                break;
+            }
+        }
+        let mid = mid.clamp(1, colors.len() - 1);
+        let upper = colors.split_off(mid);
+        boxes.push(colors);
+        boxes.push(upper);
+    }
+
+    let mut palette: Vec<[u8; 3]> = boxes.iter().map(|b| box_average(b)).collect();
+    palette.sort();
+    palette.dedup();
+    palette
+}
+
+/// Maps colors to the index of the nearest palette entry, caching lookups.
+pub struct PaletteMapper<'a> {
+    palette: &'a [[u8; 3]],
+    cache: HashMap<[u8; 3], u8>,
+}
+
+impl<'a> PaletteMapper<'a> {
+    pub fn new(palette: &'a [[u8; 3]]) -> Self {
+        Self {
+            palette,
+            cache: HashMap::new(),
+        }
+    }
+
+    pub fn index_of(&mut self, color: [u8; 3]) -> u8 {
+        if let Some(&i) = self.cache.get(&color) {
+            return i;
+        }
+        let mut best = 0usize;
+        let mut best_dist = u32::MAX;
+        for (i, p) in self.palette.iter().enumerate() {
+            let dist = color_distance(*p, color);
+            if dist < best_dist {
+                best = i;
+                best_dist = dist;
+                if dist == 0 {
+                    break;
+                }
+            }
+        }
+        self.cache.insert(color, best as u8);
+        best as u8
+    }
+}
+
+fn widest_channel(colors: &[([u8; 3], u32)]) -> (usize, u8) {
+    let mut lo = [u8::MAX; 3];
+    let mut hi = [0u8; 3];
+    for (c, _) in colors {
+        for ch in 0..3 {
+            lo[ch] = lo[ch].min(c[ch]);
+            hi[ch] = hi[ch].max(c[ch]);
+        }
+    }
+    (0..3)
+        .map(|ch| (ch, hi[ch] - lo[ch]))
+        .max_by_key(|&(_, range)| range)
+        .unwrap_or((0, 0))
+}
+
+fn box_average(colors: &[([u8; 3], u32)]) -> [u8; 3] {
+    let mut sum = [0u64; 3];
+    let mut total = 0u64;
+    for &(c, n) in colors {
+        for ch in 0..3 {
+            sum[ch] += c[ch] as u64 * n as u64;
+        }
+        total += n as u64;
+    }
+    if total == 0 {
+        return [0, 0, 0];
+    }
+    [
+        ((sum[0] + total / 2) / total) as u8,
+        ((sum[1] + total / 2) / total) as u8,
+        ((sum[2] + total / 2) / total) as u8,
+    ]
+}
+
+fn color_distance(a: [u8; 3], b: [u8; 3]) -> u32 {
+    let dr = a[0] as i32 - b[0] as i32;
+    let dg = a[1] as i32 - b[1] as i32;
+    let db = a[2] as i32 - b[2] as i32;
+    (dr * dr * 2 + dg * dg * 4 + db * db * 3) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn palette_keeps_exact_colors_when_they_fit() {
+        let px = [[255, 0, 0], [0, 0, 255], [255, 0, 0]];
+        let palette = build_palette(px.into_iter(), 16);
+        assert_eq!(palette, vec![[0, 0, 255], [255, 0, 0]]);
+    }
+
+    #[test]
+    fn palette_is_reduced_with_median_cut() {
+        let px: Vec<[u8; 3]> = (0..=255u8).map(|v| [v, v, v]).collect();
+        let palette = build_palette(px.into_iter(), 4);
+        assert_eq!(palette.len(), 4);
+
+        let mut mapper = PaletteMapper::new(&palette);
+        let dark = palette[mapper.index_of([0, 0, 0]) as usize];
+        let light = palette[mapper.index_of([255, 255, 255]) as usize];
+        assert!(dark[0] < 64);
+        assert!(light[0] > 192);
+    }
+}
@@ -0,0 +1,393 @@
+// SPDX-FileCopyrightText: 2026 (c) Gary "BLumia" Wang <opensource@blumia.net>
+//
+// SPDX-License-Identifier: MIT
+
+use crate::{
+    create::{EncodedImage, build_ico},
+    ne::{is_ne, read_ne_resources},
+    pe::{is_pe, read_pe_resources, read_u16_le, read_u32_le},
+    resfile::{is_res_file, read_res_file},
+};
+use std::fmt;
+
+pub const RT_CURSOR: u16 = 1;
+pub const RT_BITMAP: u16 = 2;
+pub const RT_ICON: u16 = 3;
+pub const RT_GROUP_CURSOR: u16 = 12;
+pub const RT_GROUP_ICON: u16 = 14;
+pub const RT_VERSION: u16 = 16;
+
+const TYPE_NAMES: [(u16, &str); 21] = [
+    (1, "cursor"),
+    (2, "bitmap"),
+    (3, "icon"),
+    (4, "menu"),
+    (5, "dialog"),
+    (6, "string"),
+    (7, "fontdir"),
+    (8, "font"),
+    (9, "accelerator"),
+    (10, "rcdata"),
+    (11, "messagelist"),
+    (12, "group_cursor"),
+    (14, "group_icon"),
+    (16, "version"),
+    (17, "dlginclude"),
+    (19, "plugplay"),
+    (20, "vxd"),
+    (21, "anicursor"),
+    (22, "aniicon"),
+    (23, "html"),
+    (24, "manifest"),
+];
+
+const GRPICONDIR_SIZE: usize = 6;
+const GRPICONDIRENTRY_SIZE: usize = 14;
+const LANG_EN_US: u16 = 1033;
+
+/// A resource type or name: either a 16-bit ordinal or a string.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ResId {
+    Id(u16),
+    Name(String),
+}
+
+impl fmt::Display for ResId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResId::Id(id) => write!(f, "{id}"),
+            ResId::Name(name) => write!(f, "'{name}'"),
+        }
+    }
+}
+
+/// One leaf of a resource tree.
+#[derive(Clone, Debug)]
+pub struct Resource {
+    pub type_id: ResId,
+    pub name: ResId,
+    /// Language ID, `None` for formats without languages.
+    pub language: Option<u16>,
+    /// Offset of the resource data in the file.
+    pub offset: usize,
+    pub data: Vec<u8>,
+}
+
+/// Reads every resource from a 32/64-bit PE or 16-bit NE executable, or from
+/// a compiled `.res` file.
+pub fn read_resources(bytes: &[u8]) -> Result<Vec<Resource>, String> {
+    if is_pe(bytes) {
+        return read_pe_resources(bytes);
+    }
+    if is_ne(bytes) {
+        return read_ne_resources(bytes);
+    }
+    if is_res_file(bytes) {
+        return read_res_file(bytes);
+    }
+    Err("not a PE, NE or resource file".to_string())
+}
+
+/// Returns the wrestool name of a predefined resource type.
+pub fn type_name(type_id: &ResId) -> Option<&'static str> {
+    let ResId::Id(id) = type_id else {
+        return None;
+    };
+    TYPE_NAMES
+        .iter()
+        .find(|(n, _)| n == id)
+        .map(|(_, name)| *name)
+}
+
+/// Rebuilds a standalone `.ico` or `.cur` file from an `RT_GROUP_ICON` or
+/// `RT_GROUP_CURSOR` resource and the `RT_ICON`/`RT_CURSOR` leaves it refers to.
+pub fn group_to_ico(group: &Resource, resources: &[Resource]) -> Result<Vec<u8>, String> {
+    let is_cursor = match group.type_id {
+        ResId::Id(RT_GROUP_ICON) => false,
+        ResId::Id(RT_GROUP_CURSOR) => true,
+        _ => return Err("not an icon or cursor group".to_string()),
+    };
+    let member_type = if is_cursor { RT_CURSOR } else { RT_ICON };
+
+    let data = &group.data;
+    let count = read_u16_le(data, 4)? as usize;
+    let mut images = Vec::with_capacity(count);
+    for i in 0..count {
+        let entry = GRPICONDIR_SIZE + i * GRPICONDIRENTRY_SIZE;
+        let size = read_u32_le(data, entry + 8)? as usize;
+        let id = read_u16_le(data, entry + 12)?;
+
+        let member = find_member(resources, member_type, id, group.language)
+            .ok_or_else(|| format!("group refers to missing resource {id}"))?;
+        let mut payload = member.data.as_slice();
+        let (mut hotspot_x, mut hotspot_y) = (0, 0);
+        if is_cursor {
+            // RT_CURSOR data is prefixed with the hotspot.
+            hotspot_x = read_u16_le(payload, 0)? as i32;
+            hotspot_y = read_u16_le(payload, 2)? as i32;
+            payload = &payload[4..];
+        }
+        // The directory size is authoritative only when it fits the leaf.
+        let payload = &payload[..size.min(payload.len())];
+        images.push(EncodedImage::from_data(
+            payload.to_vec(),
+            hotspot_x,
+            hotspot_y,
+        )?);
+    }
+
+    let container_type = if is_cursor { 2 } else { 1 };
+    Ok(build_ico(container_type, is_cursor, &images, true))
+}
+
+/// Finds the icon or cursor leaf with the given ID, preferring the language of
+/// the group that refers to it.
+fn find_member(
+    resources: &[Resource],
+    type_id: u16,
+    id: u16,
+    language: Option<u16>,
+) -> Option<&Resource> {
+    let mut candidates = resources
+        .iter()
+        .filter(|r| r.type_id == ResId::Id(type_id) && r.name == ResId::Id(id));
+    let first = candidates.clone().next();
+    candidates.find(|r| r.language == language).or(first)
+}
+
+/// An icon or cursor file split into resources.
+pub struct IconGroup {
+    pub is_cursor: bool,
+    /// Data of the `RT_GROUP_ICON` or `RT_GROUP_CURSOR` resource.
+    pub group: Vec<u8>,
+    /// Data of the `RT_ICON` or `RT_CURSOR` resources, in the order of their
+    /// IDs.
+    pub members: Vec<Vec<u8>>,
+}
+
+/// Splits an `.ico` or `.cur` file into a group directory and one member
+/// resource per image, numbering the members from `first_id`.
+pub fn icon_group_from_ico(bytes: &[u8], first_id: u16) -> Result<IconGroup, String> {
+    let kind = read_u16_le(bytes, 2)?;
+    if read_u16_le(bytes, 0)? != 0 || !matches!(kind, 1 | 2) {
+        return Err("not an icon or cursor file".to_string());
+    }
+    let is_cursor = kind == 2;
+    let count = read_u16_le(bytes, 4)? as usize;
+
+    let mut group = bytes[0..GRPICONDIR_SIZE].to_vec();
+    let mut members = Vec::with_capacity(count);
+    for i in 0..count {
+        let entry = GRPICONDIR_SIZE + i * 16;
+        let size = read_u32_le(bytes, entry + 8)? as usize;
+        let offset = read_u32_le(bytes, entry + 12)? as usize;
+        let data = bytes
+            .get(offset..offset + size)
+            .ok_or_else(|| "premature end".to_string())?;
+        let id = u16::try_from(i)
+            .ok()
+            .and_then(|i| first_id.checked_add(i))
+            .ok_or_else(|| "too many images".to_string())?;
+
+        if is_cursor {
+            // Cursor groups store the full DIB height and no hotspot; the
+            // hotspot moves in front of the image data instead.
+            let image = EncodedImage::from_data(data.to_vec(), 0, 0)?;
+            for v in [image.width, image.height * 2, 1, image.bit_depth] {
+                group.extend_from_slice(&(v as u16).to_le_bytes());
+            }
+            group.extend_from_slice(&(size as u32 + 4).to_le_bytes());
+            let mut member = bytes[entry + 4..entry + 8].to_vec();
+            member.extend_from_slice(data);
+            members.push(member);
+        } else {
+            group.extend_from_slice(&bytes[entry..entry + 12]);
+            members.push(data.to_vec());
+        }
+        group.extend_from_slice(&id.to_le_bytes());
+    }
+
+    Ok(IconGroup {
+        is_cursor,
+        group,
+        members,
+    })
+}
+
+/// Stores the images of an `.ico` file as the icon group `name`/`language`,
+/// replacing that group and the icons only it refers to.
+///
+/// Without `name` the first icon group, usually the application icon, is
+/// replaced; a new group is named 1. Without `language` the replaced group's
+/// language is kept, or 1033 (U.S. English) is used for a new group.
+pub fn set_icon_group(
+    resources: &mut Vec<Resource>,
+    ico: &[u8],
+    name: Option<ResId>,
+    language: Option<u16>,
+) -> Result<(), String> {
+    let existing = resources.iter().find(|r| {
+        r.type_id == ResId::Id(RT_GROUP_ICON)
+            && name.as_ref().is_none_or(|n| *n == r.name)
+            && language.is_none_or(|l| r.language == Some(l))
+    });
+    let language = language
+        .or(existing.and_then(|r| r.language))
+        .unwrap_or(LANG_EN_US);
+    let name = name
+        .or(existing.map(|r| r.name.clone()))
+        .unwrap_or(ResId::Id(1));
+
+    let is_target = |r: &Resource| {
+        r.type_id == ResId::Id(RT_GROUP_ICON) && r.name == name && r.language == Some(language)
+    };
+    let mut orphans = Vec::new();
+    for group in resources.iter().filter(|r| is_target(r)) {
+        orphans.extend(group_member_ids(&group.data)?);
+    }
+    resources.retain(|r| !is_target(r));
+    for group in resources
+        .iter()
+        .filter(|r| r.type_id == ResId::Id(RT_GROUP_ICON))
+    {
+        let used = group_member_ids(&group.data)?;
+        orphans.retain(|id| !used.contains(id));
+    }
+    resources.retain(|r| {
+        !(r.type_id == ResId::Id(RT_ICON)
+            && r.language == Some(language)
+            && matches!(r.name, ResId::Id(id) if orphans.contains(&id)))
+    });
+
+    let first_id = resources
+        .iter()
+        .filter(|r| r.type_id == ResId::Id(RT_ICON))
+        .filter_map(|r| match r.name {
+            ResId::Id(id) => Some(id),
+            ResId::Name(_) => None,
+        })
+        .max()
+        .map_or(Some(1), |id| id.checked_add(1))
+        .ok_or_else(|| "no free icon resource IDs".to_string())?;
+    let icon_group = icon_group_from_ico(ico, first_id)?;
+    if icon_group.is_cursor {
+        return Err("not an icon file".to_string());
+    }
+
+    for (i, data) in icon_group.members.into_iter().enumerate() {
+        resources.push(Resource {
+            type_id: ResId::Id(RT_ICON),
+            name: ResId::Id(first_id + i as u16),
+            language: Some(language),
+            offset: 0,
+            data,
+        });
+    }
+    resources.push(Resource {
+        type_id: ResId::Id(RT_GROUP_ICON),
+        name,
+        language: Some(language),
+        offset: 0,
+        data: icon_group.group,
+    });
+    Ok(())
+}
+
+/// Returns the member resource IDs listed in a group directory.
+fn group_member_ids(data: &[u8]) -> Result<Vec<u16>, String> {
+    let count = read_u16_le(data, 4)? as usize;
+    (0..count)
+        .map(|i| read_u16_le(data, GRPICONDIR_SIZE + i * GRPICONDIRENTRY_SIZE + 12))
+        .collect()
+}
+
+/// Parses a `--type` value: a number, a predefined type name such as
+/// `group_icon`, or any other string.
+pub fn parse_type_id(value: &str) -> ResId {
+    if let Some((id, _)) = TYPE_NAMES.iter().find(|(_, name)| *name == value) {
+        return ResId::Id(*id);
+    }
+    parse_name_id(value)
+}
+
+/// Parses a `--name` value: a number or a string.
+pub fn parse_name_id(value: &str) -> ResId {
+    match value.parse::<u16>() {
+        Ok(id) => ResId::Id(id),
+        Err(_) => ResId::Name(value.to_string()),
+    }
+}
+
+/// Formats the `--type`, `--name` and `--language` options selecting `res`.
+pub fn format_resource_id(res: &Resource) -> String {
+    let mut line = format!("--type={} --name={}", res.type_id, res.name);
+    if let Some(lang) = res.language {
+        line.push_str(&format!(" --language={lang}"));
+    }
+    line
+}
+
+pub fn format_resource_line(res: &Resource) -> String {
+    let mut line = format_resource_id(res);
+    line.push_str(" [");
+    if let Some(name) = type_name(&res.type_id) {
+        line.push_str(&format!("type={name} "));
+    }
+    line.push_str(&format!(
+        "offset=0x{:x} size={}]",
+        res.offset,
+        res.data.len()
+    ));
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::rgba_png;
+
+    fn resource(type_id: u16, name: u16, data: Vec<u8>) -> Resource {
+        Resource {
+            type_id: ResId::Id(type_id),
+            name: ResId::Id(name),
+            language: Some(1033),
+            offset: 0,
+            data,
+        }
+    }
+
+    fn make_png(width: u32, height: u32) -> Vec<u8> {
+        rgba_png(width, height, &vec![255; (width * height * 4) as usize])
+    }
+
+    #[test]
+    fn group_cursor_rebuilds_cur_file() {
+        let images = [make_png(16, 16), make_png(32, 32)];
+
+        let mut group = vec![0, 0, 2, 0, 2, 0];
+        let mut resources = Vec::new();
+        for (i, png) in images.iter().enumerate() {
+            let size = png.len() as u32;
+            let dim = 16 * (i as u16 + 1);
+            for v in [dim, dim * 2, 1, 32] {
+                group.extend(v.to_le_bytes());
+            }
+            group.extend(size.to_le_bytes());
+            group.extend((i as u16 + 7).to_le_bytes());
+
+            let mut data = vec![3, 0, 4, 0];
+            data.extend(png);
+            resources.push(resource(RT_CURSOR, i as u16 + 7, data));
+        }
+        let group = resource(RT_GROUP_CURSOR, 1, group);
+
+        let expected: Vec<EncodedImage> = images
+            .iter()
+            .map(|png| EncodedImage::from_data(png.clone(), 3, 4).unwrap())
+            .collect();
+        assert_eq!(
+            group_to_ico(&group, &resources).unwrap(),
+            build_ico(2, true, &expected, true)
+        );
+    }
+}
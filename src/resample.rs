@@ -0,0 +1,172 @@
+// SPDX-FileCopyrightText: 2026 (c) Gary "BLumia" Wang <opensource@blumia.net>
+//
+// SPDX-License-Identifier: MIT
+
+/// Scales top-down RGBA pixels to fit a `size` x `size` square, keeping the
+/// aspect ratio and centering the result on a transparent canvas.
+pub fn resize_to_square(width: u32, height: u32, rgba: &[u8], size: u32) -> Vec<u8> {
+    if width == size && height == size {
+        return rgba.to_vec();
+    }
+
+    let scale = f64::min(size as f64 / width as f64, size as f64 / height as f64);
+    let dst_w = ((width as f64 * scale).round() as u32).clamp(1, size);
+    let dst_h = ((height as f64 * scale).round() as u32).clamp(1, size);
+    let scaled = resize_rgba(width, height, rgba, dst_w, dst_h);
+    if dst_w == size && dst_h == size {
+        return scaled;
+    }
+
+    let mut out = vec![0u8; (size as usize) * (size as usize) * 4];
+    let x0 = ((size - dst_w) / 2) as usize;
+    let y0 = ((size - dst_h) / 2) as usize;
+    let row_len = dst_w as usize * 4;
+    for y in 0..dst_h as usize {
+        let src = &scaled[y * row_len..(y + 1) * row_len];
+        let dst_start = ((y0 + y) * size as usize + x0) * 4;
+        out[dst_start..dst_start + row_len].copy_from_slice(src);
+    }
+    out
+}
+
+/// Resamples top-down RGBA pixels with a Lanczos-3 filter.
+///
+/// Filtering happens on premultiplied alpha so that the color of fully
+/// transparent pixels does not bleed into the edges of the result.
+pub fn resize_rgba(src_w: u32, src_h: u32, src: &[u8], dst_w: u32, dst_h: u32) -> Vec<u8> {
+    let (src_w, src_h) = (src_w as usize, src_h as usize);
+    let (dst_w, dst_h) = (dst_w as usize, dst_h as usize);
+
+    let premultiplied: Vec<[f32; 4]> = src
+        .chunks_exact(4)
+        .map(|px| {
+            let a = px[3] as f32 / 255.0;
+            [
+                px[0] as f32 * a,
+                px[1] as f32 * a,
+                px[2] as f32 * a,
+                px[3] as f32,
+            ]
+        })
+        .collect();
+
+    let x_weights = filter_weights(src_w, dst_w);
+    let mut horizontal = vec![[0f32; 4]; dst_w * src_h];
+    for y in 0..src_h {
+        for (x, (start, weights)) in x_weights.iter().enumerate() {
+            let mut acc = [0f32; 4];
+            for (k, w) in weights.iter().enumerate() {
+                let px = premultiplied[y * src_w + start + k];
+                for c in 0..4 {
+                    acc[c] += px[c] * w;
+                }
+            }
+            horizontal[y * dst_w + x] = acc;
+        }
+    }
+
+    let y_weights = filter_weights(src_h, dst_h);
+    let mut out = vec![0u8; dst_w * dst_h * 4];
+    for (y, (start, weights)) in y_weights.iter().enumerate() {
+        for x in 0..dst_w {
+            let mut acc = [0f32; 4];
+            for (k, w) in weights.iter().enumerate() {
+                let px = horizontal[(start + k) * dst_w + x];
+                for c in 0..4 {
+                    acc[c] += px[c] * w;
+                }
+            }
+
+            let alpha = acc[3].clamp(0.0, 255.0);
+            let i = (y * dst_w + x) * 4;
+            if alpha > 0.0 {
+                let unpremultiply = 255.0 / alpha;
+                for c in 0..3 {
+                    out[i + c] = (acc[c] * unpremultiply).round().clamp(0.0, 255.0) as u8;
+                }
+            }
+            out[i + 3] = alpha.round() as u8;
+        }
+    }
+    out
+}
+
+fn filter_weights(src_len: usize, dst_len: usize) -> Vec<(usize, Vec<f32>)> {
+    const SUPPORT: f64 = 3.0;
+
+    let scale = src_len as f64 / dst_len as f64;
+    let filter_scale = scale.max(1.0);
+    let radius = SUPPORT * filter_scale;
+
+    (0..dst_len)
+        .map(|i| {
+            let center = (i as f64 + 0.5) * scale;
+            let start = ((center - radius).floor().max(0.0)) as usize;
+            let end = (((center + radius).ceil()) as usize).min(src_len);
+            let mut weights: Vec<f64> = (start..end)
+                .map(|j| lanczos3((j as f64 + 0.5 - center) / filter_scale))
+                .collect();
+            let sum: f64 = weights.iter().sum();
+            if sum != 0.0 {
+                for w in &mut weights {
+                    *w /= sum;
+                }
+            }
+            (start, weights.into_iter().map(|w| w as f32).collect())
+        })
+        .collect()
+}
+
+fn lanczos3(x: f64) -> f64 {
+    if x == 0.0 {
+        return 1.0;
+    }
+    if x.abs() >= 3.0 {
+        return 0.0;
+    }
+    let px = std::f64::consts::PI * x;
+    3.0 * px.sin() * (px / 3.0).sin() / (px * px)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resize_keeps_solid_color() {
+        let src: Vec<u8> = [12u8, 34, 56, 255].repeat(64 * 64);
+        let out = resize_rgba(64, 64, &src, 16, 16);
+        assert_eq!(out.len(), 16 * 16 * 4);
+        assert!(out.chunks_exact(4).all(|px| px == [12, 34, 56, 255]));
+    }
+
+    #[test]
+    fn resize_does_not_bleed_transparent_color() {
+        // Left half transparent black, right half opaque red.
+        let mut src = Vec::new();
+        for _ in 0..8 {
+            for x in 0..8 {
+                if x < 4 {
+                    src.extend_from_slice(&[0, 0, 0, 0]);
+                } else {
+                    src.extend_from_slice(&[255, 0, 0, 255]);
+                }
+            }
+        }
+        let out = resize_rgba(8, 8, &src, 3, 3);
+        for px in out.chunks_exact(4) {
+            if px[3] > 0 {
+                assert!(px[0] > 240, "{px:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn resize_to_square_centers_non_square_images() {
+        let src: Vec<u8> = [255u8, 255, 255, 255].repeat(32 * 16);
+        let out = resize_to_square(32, 16, &src, 16);
+        assert_eq!(out.len(), 16 * 16 * 4);
+        assert_eq!(out[3], 0);
+        assert_eq!(out[(8 * 16 + 8) * 4 + 3], 255);
+    }
+}
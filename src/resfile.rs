@@ -0,0 +1,188 @@
+// SPDX-FileCopyrightText: 2026 (c) Gary "BLumia" Wang <opensource@blumia.net>
+//
+// SPDX-License-Identifier: MIT
+
+use crate::{
+    pe::{read_u16_le, read_u32_le},
+    res::{RT_GROUP_CURSOR, RT_GROUP_ICON, ResId, Resource},
+};
+
+const MOVEABLE: u16 = 0x0010;
+const PURE: u16 = 0x0020;
+const DISCARDABLE: u16 = 0x1000;
+
+/// The empty record that starts every 32-bit `.res` file.
+const RES_SIGNATURE: [u8; 16] = [
+    0, 0, 0, 0, 0x20, 0, 0, 0, 0xff, 0xff, 0, 0, 0xff, 0xff, 0, 0,
+];
+
+pub fn is_res_file(bytes: &[u8]) -> bool {
+    bytes.starts_with(&RES_SIGNATURE)
+}
+
+/// Reads every resource from a 32-bit `.res` file.
+pub fn read_res_file(bytes: &[u8]) -> Result<Vec<Resource>, String> {
+    if !is_res_file(bytes) {
+        return Err("not a resource file".to_string());
+    }
+
+    let mut out = Vec::new();
+    let mut pos = 0usize;
+    while pos + 8 <= bytes.len() {
+        let data_size = read_u32_le(bytes, pos)? as usize;
+        let header_size = read_u32_le(bytes, pos + 4)? as usize;
+        let (type_id, next) = read_id(bytes, pos + 8)?;
+        let (name, next) = read_id(bytes, next)?;
+        let fixed = pos + (next - pos).next_multiple_of(4);
+        let language = read_u16_le(bytes, fixed + 6)?;
+        // The header must at least cover what was just read, otherwise a
+        // zero-sized record would never advance.
+        if header_size < fixed + 16 - pos {
+            return Err("invalid resource header".to_string());
+        }
+
+        let data_start = pos + header_size;
+        let data = bytes
+            .get(data_start..data_start + data_size)
+            .ok_or_else(|| "premature end".to_string())?;
+        // Skip the empty records used as file signature and padding.
+        if !(data_size == 0 && type_id == ResId::Id(0)) {
+            out.push(Resource {
+                type_id,
+                name,
+                language: Some(language),
+                offset: data_start,
+                data: data.to_vec(),
+            });
+        }
+        pos = (data_start + data_size).next_multiple_of(4);
+    }
+    Ok(out)
+}
+
+/// Reads an ordinal or NUL-terminated UTF-16 name, returning it and the
+/// offset right after it.
+fn read_id(bytes: &[u8], offset: usize) -> Result<(ResId, usize), String> {
+    let first = read_u16_le(bytes, offset)?;
+    if first == 0xffff {
+        return Ok((ResId::Id(read_u16_le(bytes, offset + 2)?), offset + 4));
+    }
+    let mut units = Vec::new();
+    let mut pos = offset;
+    loop {
+        let unit = read_u16_le(bytes, pos)?;
+        pos += 2;
+        if unit == 0 {
+            break;
+        }
+        units.push(unit);
+    }
+    Ok((ResId::Name(String::from_utf16_lossy(&units)), pos))
+}
+
+/// Builds a 32-bit `.res` file as written by `rc.exe`: an empty header
+/// record followed by one RESOURCEHEADER and data block per resource.
+pub fn build_res_file(resources: &[Resource]) -> Vec<u8> {
+    let mut out = Vec::new();
+    // The leading empty record marks the file as 32-bit.
+    write_record(&mut out, &ResId::Id(0), &ResId::Id(0), 0, 0, &[]);
+    for res in resources {
+        let flags = match res.type_id {
+            ResId::Id(RT_GROUP_ICON | RT_GROUP_CURSOR) => MOVEABLE | PURE | DISCARDABLE,
+            _ => MOVEABLE | DISCARDABLE,
+        };
+        write_record(
+            &mut out,
+            &res.type_id,
+            &res.name,
+            flags,
+            res.language.unwrap_or(0),
+            &res.data,
+        );
+    }
+    out
+}
+
+fn write_record(
+    out: &mut Vec<u8>,
+    type_id: &ResId,
+    name: &ResId,
+    memory_flags: u16,
+    language: u16,
+    data: &[u8],
+) {
+    let mut ids = Vec::new();
+    write_id(&mut ids, type_id);
+    write_id(&mut ids, name);
+    ids.resize(ids.len().next_multiple_of(4), 0);
+
+    let header_size = 8 + ids.len() + 16;
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out.extend_from_slice(&(header_size as u32).to_le_bytes());
+    out.extend_from_slice(&ids);
+    out.extend_from_slice(&0u32.to_le_bytes()); // DataVersion
+    out.extend_from_slice(&memory_flags.to_le_bytes());
+    out.extend_from_slice(&language.to_le_bytes());
+    out.extend_from_slice(&0u32.to_le_bytes()); // Version
+    out.extend_from_slice(&0u32.to_le_bytes()); // Characteristics
+    out.extend_from_slice(data);
+    out.resize(out.len().next_multiple_of(4), 0);
+}
+
+/// Writes an ordinal as 0xFFFF followed by the ID, or a NUL-terminated UTF-16
+/// string.
+fn write_id(out: &mut Vec<u8>, id: &ResId) {
+    match id {
+        ResId::Id(n) => {
+            out.extend_from_slice(&0xffffu16.to_le_bytes());
+            out.extend_from_slice(&n.to_le_bytes());
+        }
+        ResId::Name(s) => {
+            for unit in s.encode_utf16().chain([0]) {
+                out.extend_from_slice(&unit.to_le_bytes());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn res_file_writes_empty_header_and_padded_records() {
+        let res = Resource {
+            type_id: ResId::Id(RT_GROUP_ICON),
+            name: ResId::Name("APP".to_string()),
+            language: Some(1033),
+            offset: 0,
+            data: vec![1, 2, 3],
+        };
+        let out = build_res_file(std::slice::from_ref(&res));
+
+        let mut expected = vec![0, 0, 0, 0, 32, 0, 0, 0, 0xff, 0xff, 0, 0, 0xff, 0xff, 0, 0];
+        expected.extend([0; 16]);
+        expected.extend([3, 0, 0, 0, 36, 0, 0, 0, 0xff, 0xff, 14, 0]);
+        expected.extend([b'A', 0, b'P', 0, b'P', 0, 0, 0]);
+        expected.extend([0, 0, 0, 0, 0x30, 0x10, 0x09, 0x04]);
+        expected.extend([0; 8]);
+        expected.extend([1, 2, 3, 0]);
+        assert_eq!(out, expected);
+
+        let read = read_res_file(&out).unwrap();
+        assert_eq!(read.len(), 1);
+        assert_eq!(
+            (&read[0].type_id, &read[0].name, read[0].language),
+            (&res.type_id, &res.name, res.language)
+        );
+        assert_eq!(read[0].data, res.data);
+    }
+
+    #[test]
+    fn res_file_rejects_header_size_shorter_than_header() {
+        let mut bytes = build_res_file(&[]);
+        bytes.extend([0, 0, 0, 0, 0, 0, 0, 0, 0xff, 0xff, 3, 0, 0xff, 0xff, 1, 0]);
+        bytes.extend([0; 16]);
+        assert!(read_res_file(&bytes).is_err());
+    }
+}
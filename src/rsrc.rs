@@ -0,0 +1,212 @@
+// SPDX-FileCopyrightText: 2026 (c) Gary "BLumia" Wang <opensource@blumia.net>
+//
+// SPDX-License-Identifier: MIT
+
+use crate::res::{ResId, Resource};
+use std::cmp::Ordering;
+
+const DIRECTORY_SIZE: usize = 16;
+const DIRECTORY_ENTRY_SIZE: usize = 8;
+const DATA_ENTRY_SIZE: usize = 16;
+
+/// A serialized resource tree, as stored in a `.rsrc` section.
+pub struct ResourceSection {
+    pub data: Vec<u8>,
+    /// Offsets of the `OffsetToData` field of every data entry. These hold
+    /// RVAs and need relocating when the section is not placed at `base_rva`.
+    pub data_rva_fields: Vec<usize>,
+}
+
+struct NameNode<'a> {
+    id: &'a ResId,
+    string_offset: usize,
+    dir_offset: usize,
+    leaves: Vec<&'a Resource>,
+}
+
+struct TypeNode<'a> {
+    id: &'a ResId,
+    string_offset: usize,
+    dir_offset: usize,
+    names: Vec<NameNode<'a>>,
+}
+
+/// Serializes `resources` into a type/name/language resource tree whose data
+/// entries point at RVAs starting from `base_rva`.
+pub fn build_resource_section(resources: &[Resource], base_rva: u32) -> ResourceSection {
+    let mut sorted: Vec<&Resource> = resources.iter().collect();
+    sorted.sort_by(|a, b| {
+        cmp_id(&a.type_id, &b.type_id)
+            .then_with(|| cmp_id(&a.name, &b.name))
+            .then_with(|| a.language.unwrap_or(0).cmp(&b.language.unwrap_or(0)))
+    });
+
+    let mut types: Vec<TypeNode> = Vec::new();
+    for res in sorted {
+        if types.last().is_none_or(|t| *t.id != res.type_id) {
+            types.push(TypeNode {
+                id: &res.type_id,
+                string_offset: 0,
+                dir_offset: 0,
+                names: Vec::new(),
+            });
+        }
+        let names = &mut types.last_mut().unwrap().names;
+        if names.last().is_none_or(|n| *n.id != res.name) {
+            names.push(NameNode {
+                id: &res.name,
+                string_offset: 0,
+                dir_offset: 0,
+                leaves: Vec::new(),
+            });
+        }
+        names.last_mut().unwrap().leaves.push(res);
+    }
+
+    // Layout: all directories, then name strings, then data entries, then data.
+    let mut offset = dir_size(types.len());
+    for t in &mut types {
+        t.dir_offset = offset;
+        offset += dir_size(t.names.len());
+    }
+    for t in &mut types {
+        for n in &mut t.names {
+            n.dir_offset = offset;
+            offset += dir_size(n.leaves.len());
+        }
+    }
+    for t in &mut types {
+        if let ResId::Name(s) = t.id {
+            t.string_offset = offset;
+            offset += string_size(s);
+        }
+        for n in &mut t.names {
+            if let ResId::Name(s) = n.id {
+                n.string_offset = offset;
+                offset += string_size(s);
+            }
+        }
+    }
+    let data_entries_offset = align(offset, 4);
+    let mut data_offset = data_entries_offset + resources.len() * DATA_ENTRY_SIZE;
+
+    let mut out = vec![0u8; data_offset];
+    let mut data_rva_fields = Vec::with_capacity(resources.len());
+
+    write_directory(&mut out, 0, types.iter().map(|t| (t.id, t.string_offset)));
+    for (i, t) in types.iter().enumerate() {
+        put_subdir(&mut out, entry_offset(0, i), t.dir_offset);
+        write_directory(
+            &mut out,
+            t.dir_offset,
+            t.names.iter().map(|n| (n.id, n.string_offset)),
+        );
+    }
+
+    let mut data_entry = data_entries_offset;
+    for t in &types {
+        if let ResId::Name(s) = t.id {
+            put_string(&mut out, t.string_offset, s);
+        }
+        for (i, n) in t.names.iter().enumerate() {
+            if let ResId::Name(s) = n.id {
+                put_string(&mut out, n.string_offset, s);
+            }
+            put_subdir(&mut out, entry_offset(t.dir_offset, i), n.dir_offset);
+
+            let langs: Vec<u16> = n.leaves.iter().map(|r| r.language.unwrap_or(0)).collect();
+            put_u16(&mut out, n.dir_offset + 14, langs.len() as u16);
+            for (j, (res, lang)) in n.leaves.iter().zip(langs).enumerate() {
+                let entry = entry_offset(n.dir_offset, j);
+                put_u32(&mut out, entry, lang as u32);
+                put_u32(&mut out, entry + 4, data_entry as u32);
+
+                data_offset = align(data_offset, 8);
+                out.resize(data_offset, 0);
+                out.extend_from_slice(&res.data);
+                put_u32(&mut out, data_entry, base_rva + data_offset as u32);
+                put_u32(&mut out, data_entry + 4, res.data.len() as u32);
+                data_rva_fields.push(data_entry);
+                data_offset += res.data.len();
+                data_entry += DATA_ENTRY_SIZE;
+            }
+        }
+    }
+
+    ResourceSection {
+        data: out,
+        data_rva_fields,
+    }
+}
+
+/// Resource directories list named entries first, ordered by name, then
+/// numbered entries in ascending order.
+fn cmp_id(a: &ResId, b: &ResId) -> Ordering {
+    match (a, b) {
+        (ResId::Name(a), ResId::Name(b)) => a.encode_utf16().cmp(b.encode_utf16()),
+        (ResId::Name(_), ResId::Id(_)) => Ordering::Less,
+        (ResId::Id(_), ResId::Name(_)) => Ordering::Greater,
+        (ResId::Id(a), ResId::Id(b)) => a.cmp(b),
+    }
+}
+
+/// Writes a directory header and the name half of its entries.
+fn write_directory<'a>(
+    out: &mut [u8],
+    offset: usize,
+    ids: impl Iterator<Item = (&'a ResId, usize)>,
+) {
+    let mut named = 0u16;
+    let mut numbered = 0u16;
+    for (i, (id, string_offset)) in ids.enumerate() {
+        let field = match id {
+            ResId::Name(_) => {
+                named += 1;
+                0x8000_0000 | string_offset as u32
+            }
+            ResId::Id(n) => {
+                numbered += 1;
+                *n as u32
+            }
+        };
+        put_u32(out, entry_offset(offset, i), field);
+    }
+    put_u16(out, offset + 12, named);
+    put_u16(out, offset + 14, numbered);
+}
+
+fn put_subdir(out: &mut [u8], entry: usize, dir_offset: usize) {
+    put_u32(out, entry + 4, 0x8000_0000 | dir_offset as u32);
+}
+
+fn put_string(out: &mut [u8], offset: usize, s: &str) {
+    let units: Vec<u16> = s.encode_utf16().collect();
+    put_u16(out, offset, units.len() as u16);
+    for (i, unit) in units.iter().enumerate() {
+        put_u16(out, offset + 2 + i * 2, *unit);
+    }
+}
+
+fn dir_size(entries: usize) -> usize {
+    DIRECTORY_SIZE + entries * DIRECTORY_ENTRY_SIZE
+}
+
+fn entry_offset(dir_offset: usize, index: usize) -> usize {
+    dir_offset + DIRECTORY_SIZE + index * DIRECTORY_ENTRY_SIZE
+}
+
+fn string_size(s: &str) -> usize {
+    2 + s.encode_utf16().count() * 2
+}
+
+pub fn align(value: usize, alignment: usize) -> usize {
+    value.div_ceil(alignment) * alignment
+}
+
+fn put_u16(out: &mut [u8], offset: usize, v: u16) {
+    out[offset..offset + 2].copy_from_slice(&v.to_le_bytes());
+}
+
+fn put_u32(out: &mut [u8], offset: usize, v: u32) {
+    out[offset..offset + 4].copy_from_slice(&v.to_le_bytes());
+}
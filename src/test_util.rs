@@ -0,0 +1,26 @@
+// SPDX-FileCopyrightText: 2026 (c) Gary "BLumia" Wang <opensource@blumia.net>
+//
+// SPDX-License-Identifier: MIT
+
+//! Helpers shared by the unit tests of several modules.
+
+use crate::types::ParsedArgs;
+
+/// Encodes top-down RGBA pixels as an 8-bit RGBA PNG.
+pub fn rgba_png(width: u32, height: u32, rgba: &[u8]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut buf, width, height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(rgba).unwrap();
+    }
+    buf
+}
+
+/// Parses an icotool command line, which must name a command.
+pub fn icotool_args(argv: &[&str]) -> ParsedArgs {
+    let argv: Vec<String> = argv.iter().map(|s| s.to_string()).collect();
+    crate::cli::parse_args(&argv).unwrap().1.unwrap()
+}
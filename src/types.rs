@@ -2,6 +2,8 @@
 //
 // SPDX-License-Identifier: MIT
 
+use crate::res::ResId;
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Command {
     Extract,
@@ -9,6 +11,31 @@ pub enum Command {
     Create,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Ico,
+    Ani,
+    /// Compiled 32-bit resource file.
+    Res,
+    /// COFF object file with a `.rsrc` section.
+    Coff,
+    /// OS/2 bitmap array of icons or pointers.
+    Os2,
+    /// Extracted entries as PNG images.
+    Png,
+    /// Extracted entries as `.bmp` files without the AND mask.
+    Bmp,
+    /// Extracted entries as the bare DIB stored in the icon.
+    Dib,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Machine {
+    X86,
+    X64,
+    Arm64,
+}
+
 #[derive(Clone, Debug)]
 pub struct CreateInput {
     pub path: String,
@@ -16,6 +43,7 @@ pub struct CreateInput {
     pub min_bit_depth: i32,
     pub hotspot_x: i32,
     pub hotspot_y: i32,
+    pub rate: i32,
 }
 
 #[derive(Clone, Debug)]
@@ -25,6 +53,7 @@ pub struct ParsedArgs {
     pub output: Option<String>,
 
     pub image_index: i32,
+    pub frame: i32,
     pub width: i32,
     pub height: i32,
     pub bit_depth: i32,
@@ -37,6 +66,19 @@ pub struct ParsedArgs {
     pub icon_only: bool,
     pub cursor_only: bool,
     pub compat_png_bitcount: bool,
+    /// Also write the AND mask of DIB entries when extracting.
+    pub extract_mask: bool,
+    /// Name template for extracted files, see `extract::expand_template`.
+    pub template: Option<String>,
+    /// Keep existing files instead of overwriting them.
+    pub no_clobber: bool,
+    pub sizes: Vec<u32>,
+    pub png_threshold: i32,
+    pub format: Option<Format>,
+    pub sequence: Vec<u32>,
+    pub machine: Machine,
+    pub resource_id: u16,
+    pub resource_language: u16,
 
     pub files: Vec<String>,
     pub create_inputs: Vec<CreateInput>,
@@ -44,6 +86,8 @@ pub struct ParsedArgs {
 
 #[derive(Clone, Debug)]
 pub struct EntryMeta {
+    /// 1-based frame number within an animated cursor, 0 otherwise.
+    pub frame: i32,
     pub index: i32,
     pub width: i32,
     pub height: i32,
@@ -54,6 +98,30 @@ pub struct EntryMeta {
     pub hotspot_y: i32,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WresCommand {
+    List,
+    Extract,
+    VersionInfo,
+    SetIcon,
+}
+
+#[derive(Clone, Debug)]
+pub struct WresArgs {
+    pub command: WresCommand,
+    pub output: Option<String>,
+    pub type_id: Option<ResId>,
+    pub name: Option<ResId>,
+    pub language: Option<u16>,
+    pub all: bool,
+    pub raw: bool,
+    pub json: bool,
+    /// Icon file to store with `--set-icon`.
+    pub icon: Option<String>,
+    pub no_clobber: bool,
+    pub files: Vec<String>,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Action {
     Run,
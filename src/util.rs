@@ -72,3 +72,18 @@ pub fn parse_i32(field: &str, value: &str) -> Result<i32, String> {
     }
     Ok(n)
 }
+
+pub fn parse_i32_list(field: &str, value: &str) -> Result<Vec<i32>, String> {
+    value
+        .split(',')
+        .map(|item| parse_i32(field, item.trim()))
+        .collect()
+}
+
+pub fn parse_size_list(field: &str, value: &str) -> Result<Vec<u32>, String> {
+    let sizes = parse_i32_list(field, value)?;
+    if sizes.iter().any(|n| !(1..=256).contains(n)) {
+        return Err(format!("invalid {field} value: {value}"));
+    }
+    Ok(sizes.into_iter().map(|n| n as u32).collect())
+}
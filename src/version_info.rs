@@ -0,0 +1,353 @@
+// SPDX-FileCopyrightText: 2026 (c) Gary "BLumia" Wang <opensource@blumia.net>
+//
+// SPDX-License-Identifier: MIT
+
+use crate::pe::{read_u16_le, read_u32_le};
+
+const VS_FFI_SIGNATURE: u32 = 0xfeef_04bd;
+const VS_FIXEDFILEINFO_SIZE: usize = 52;
+const BLOCK_HEADER_SIZE: usize = 6;
+
+/// The numeric part of a version resource (VS_FIXEDFILEINFO).
+pub struct FixedFileInfo {
+    pub file_version: [u16; 4],
+    pub product_version: [u16; 4],
+    pub file_flags_mask: u32,
+    pub file_flags: u32,
+    pub file_os: u32,
+    pub file_type: u32,
+    pub file_subtype: u32,
+    pub file_date: u64,
+}
+
+/// A decoded RT_VERSION resource.
+pub struct VersionInfo {
+    pub fixed: Option<FixedFileInfo>,
+    /// StringFileInfo tables keyed by their language/codepage string, e.g.
+    /// `040904b0`, each holding key/value pairs in file order.
+    pub strings: Vec<(String, Vec<(String, String)>)>,
+    /// VarFileInfo `Translation` pairs of language ID and codepage.
+    pub translations: Vec<(u16, u16)>,
+}
+
+/// One node of the version resource tree.
+struct Block<'a> {
+    key: String,
+    is_text: bool,
+    value: &'a [u8],
+    children: Vec<Block<'a>>,
+}
+
+pub fn parse_version_info(data: &[u8]) -> Result<VersionInfo, String> {
+    let (root, _) = parse_block(data, 0)?;
+    if root.key != "VS_VERSION_INFO" {
+        return Err("not a version resource".to_string());
+    }
+
+    let fixed = if root.value.len() >= VS_FIXEDFILEINFO_SIZE {
+        Some(parse_fixed(root.value)?)
+    } else {
+        None
+    };
+
+    let mut info = VersionInfo {
+        fixed,
+        strings: Vec::new(),
+        translations: Vec::new(),
+    };
+    for child in &root.children {
+        match child.key.as_str() {
+            "StringFileInfo" => {
+                for table in &child.children {
+                    let pairs = table
+                        .children
+                        .iter()
+                        .map(|s| (s.key.clone(), text_value(s)))
+                        .collect();
+                    info.strings.push((table.key.clone(), pairs));
+                }
+            }
+            "VarFileInfo" => {
+                for var in child.children.iter().filter(|v| v.key == "Translation") {
+                    for pair in var.value.chunks_exact(4) {
+                        info.translations.push((
+                            u16::from_le_bytes([pair[0], pair[1]]),
+                            u16::from_le_bytes([pair[2], pair[3]]),
+                        ));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(info)
+}
+
+fn parse_fixed(value: &[u8]) -> Result<FixedFileInfo, String> {
+    if read_u32_le(value, 0)? != VS_FFI_SIGNATURE {
+        return Err("invalid fixed file info signature".to_string());
+    }
+    let version = |offset: usize| -> Result<[u16; 4], String> {
+        let ms = read_u32_le(value, offset)?;
+        let ls = read_u32_le(value, offset + 4)?;
+        Ok([(ms >> 16) as u16, ms as u16, (ls >> 16) as u16, ls as u16])
+    };
+    Ok(FixedFileInfo {
+        file_version: version(8)?,
+        product_version: version(16)?,
+        file_flags_mask: read_u32_le(value, 24)?,
+        file_flags: read_u32_le(value, 28)?,
+        file_os: read_u32_le(value, 32)?,
+        file_type: read_u32_le(value, 36)?,
+        file_subtype: read_u32_le(value, 40)?,
+        file_date: ((read_u32_le(value, 44)? as u64) << 32) | read_u32_le(value, 48)? as u64,
+    })
+}
+
+/// Parses the block at `offset`, returning it and the offset of its sibling.
+fn parse_block(data: &[u8], offset: usize) -> Result<(Block<'_>, usize), String> {
+    let length = read_u16_le(data, offset)? as usize;
+    let value_length = read_u16_le(data, offset + 2)? as usize;
+    let is_text = read_u16_le(data, offset + 4)? == 1;
+    if length < BLOCK_HEADER_SIZE {
+        return Err("invalid version resource block".to_string());
+    }
+    let end = offset + length;
+    if end > data.len() {
+        return Err("premature end".to_string());
+    }
+
+    let mut pos = offset + BLOCK_HEADER_SIZE;
+    let mut key_units = Vec::new();
+    loop {
+        let unit = read_u16_le(data, pos)?;
+        pos += 2;
+        if unit == 0 {
+            break;
+        }
+        key_units.push(unit);
+    }
+    let key = String::from_utf16_lossy(&key_units);
+
+    // Text values count UTF-16 units; binary values count bytes.
+    let value_start = align4(pos).min(end);
+    let value_bytes = if is_text {
+        value_length * 2
+    } else {
+        value_length
+    };
+    let value_end = (value_start + value_bytes).min(end);
+    let value = &data[value_start..value_end];
+
+    let mut children = Vec::new();
+    let mut child = align4(value_end);
+    while child + BLOCK_HEADER_SIZE <= end {
+        let (block, next) = parse_block(&data[..end], child)?;
+        children.push(block);
+        child = align4(next);
+    }
+
+    Ok((
+        Block {
+            key,
+            is_text,
+            value,
+            children,
+        },
+        end,
+    ))
+}
+
+fn text_value(block: &Block<'_>) -> String {
+    if !block.is_text {
+        return String::new();
+    }
+    let units: Vec<u16> = block
+        .value
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .take_while(|&u| u != 0)
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+fn align4(offset: usize) -> usize {
+    (offset + 3) & !3
+}
+
+fn format_version(v: &[u16; 4]) -> String {
+    format!("{}.{}.{}.{}", v[0], v[1], v[2], v[3])
+}
+
+/// Formats a version resource as `KEY=VALUE` lines.
+pub fn format_version_text(info: &VersionInfo) -> String {
+    let mut out = String::new();
+    if let Some(fixed) = &info.fixed {
+        for (key, value) in fixed_fields(fixed) {
+            out.push_str(&format!("VS_FIXEDFILEINFO.{key}={value}\n"));
+        }
+    }
+    for (table, pairs) in &info.strings {
+        for (key, value) in pairs {
+            out.push_str(&format!("StringFileInfo.{table}.{key}={value}\n"));
+        }
+    }
+    if !info.translations.is_empty() {
+        let translations: Vec<String> = info
+            .translations
+            .iter()
+            .map(|(lang, cp)| format!("0x{lang:04x}:{cp}"))
+            .collect();
+        out.push_str(&format!(
+            "VarFileInfo.Translation={}\n",
+            translations.join(",")
+        ));
+    }
+    out
+}
+
+/// Formats a version resource as a JSON object.
+pub fn format_version_json(info: &VersionInfo) -> String {
+    let mut fields = Vec::new();
+    if let Some(fixed) = &info.fixed {
+        let members: Vec<String> = fixed_fields(fixed)
+            .iter()
+            .map(|(k, v)| format!("{}:{}", json_string(k), json_string(v)))
+            .collect();
+        fields.push(format!("\"fixed\":{{{}}}", members.join(",")));
+    }
+
+    let tables: Vec<String> = info
+        .strings
+        .iter()
+        .map(|(table, pairs)| {
+            let members: Vec<String> = pairs
+                .iter()
+                .map(|(k, v)| format!("{}:{}", json_string(k), json_string(v)))
+                .collect();
+            format!("{}:{{{}}}", json_string(table), members.join(","))
+        })
+        .collect();
+    fields.push(format!("\"strings\":{{{}}}", tables.join(",")));
+
+    let translations: Vec<String> = info
+        .translations
+        .iter()
+        .map(|(lang, cp)| format!("{{\"language\":{lang},\"codepage\":{cp}}}"))
+        .collect();
+    fields.push(format!("\"translations\":[{}]", translations.join(",")));
+
+    format!("{{{}}}", fields.join(","))
+}
+
+fn fixed_fields(fixed: &FixedFileInfo) -> [(&'static str, String); 8] {
+    [
+        ("FileVersion", format_version(&fixed.file_version)),
+        ("ProductVersion", format_version(&fixed.product_version)),
+        ("FileFlagsMask", format!("0x{:08x}", fixed.file_flags_mask)),
+        ("FileFlags", format!("0x{:08x}", fixed.file_flags)),
+        ("FileOS", format!("0x{:08x}", fixed.file_os)),
+        ("FileType", format!("0x{:08x}", fixed.file_type)),
+        ("FileSubtype", format!("0x{:08x}", fixed.file_subtype)),
+        ("FileDate", format!("0x{:016x}", fixed.file_date)),
+    ]
+}
+
+fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for ch in s.chars() {
+        match ch {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Serializes a version block, padding key and value to 4-byte boundaries.
+    fn block(key: &str, value: &[u8], text_units: Option<usize>, children: &[Vec<u8>]) -> Vec<u8> {
+        let mut out = vec![0u8; BLOCK_HEADER_SIZE];
+        for unit in key.encode_utf16().chain([0]) {
+            out.extend(unit.to_le_bytes());
+        }
+        out.resize(align4(out.len()), 0);
+        out.extend(value);
+        for child in children {
+            out.resize(align4(out.len()), 0);
+            out.extend(child);
+        }
+        let length = out.len() as u16;
+        let value_length = text_units.unwrap_or(value.len()) as u16;
+        out[0..2].copy_from_slice(&length.to_le_bytes());
+        out[2..4].copy_from_slice(&value_length.to_le_bytes());
+        out[4..6].copy_from_slice(&(text_units.is_some() as u16).to_le_bytes());
+        out
+    }
+
+    fn text(key: &str, value: &str) -> Vec<u8> {
+        let units: Vec<u16> = value.encode_utf16().chain([0]).collect();
+        let bytes: Vec<u8> = units.iter().flat_map(|u| u.to_le_bytes()).collect();
+        block(key, &bytes, Some(units.len()), &[])
+    }
+
+    #[test]
+    fn version_info_decodes_fixed_strings_and_translations() {
+        let mut fixed = Vec::new();
+        for v in [
+            VS_FFI_SIGNATURE,
+            0x1_0000,
+            0x0001_0002,
+            0x0003_0004,
+            0x0001_0002,
+            0,
+            0x3f,
+            0,
+            0x40004,
+            1,
+            0,
+            0,
+            0,
+        ] {
+            fixed.extend(v.to_le_bytes());
+        }
+        let table = block(
+            "040904b0",
+            &[],
+            None,
+            &[
+                text("ProductName", "Demo \"App\""),
+                text("FileVersion", "1.2.3.4"),
+            ],
+        );
+        let strings = block("StringFileInfo", &[], Some(0), &[table]);
+        let var = block("Translation", &[0x09, 0x04, 0xb0, 0x04], None, &[]);
+        let vars = block("VarFileInfo", &[], Some(0), &[var]);
+        let root = block("VS_VERSION_INFO", &fixed, None, &[strings, vars]);
+
+        let info = parse_version_info(&root).unwrap();
+        assert_eq!(info.fixed.as_ref().unwrap().file_version, [1, 2, 3, 4]);
+        assert_eq!(info.translations, vec![(0x0409, 1200)]);
+
+        let text = format_version_text(&info);
+        assert!(text.contains("VS_FIXEDFILEINFO.FileVersion=1.2.3.4\n"));
+        assert!(text.contains("StringFileInfo.040904b0.ProductName=Demo \"App\"\n"));
+        assert!(text.ends_with("VarFileInfo.Translation=0x0409:1200\n"));
+
+        let json = format_version_json(&info);
+        assert!(json.contains(
+            "\"strings\":{\"040904b0\":{\"ProductName\":\"Demo \\\"App\\\"\",\"FileVersion\":\"1.2.3.4\"}}"
+        ));
+        assert!(json.ends_with("\"translations\":[{\"language\":1033,\"codepage\":1200}]}"));
+    }
+}
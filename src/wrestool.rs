@@ -0,0 +1,575 @@
+// SPDX-FileCopyrightText: 2026 (c) Gary "BLumia" Wang <opensource@blumia.net>
+//
+// SPDX-License-Identifier: MIT
+
+use std::io::{self, Write};
+
+use crate::{
+    dib::dib_to_bmp_file,
+    input::read_input,
+    output::{OutputNames, write_file},
+    pe::{is_pe, replace_pe_resources},
+    res::{
+        RT_BITMAP, RT_GROUP_CURSOR, RT_GROUP_ICON, RT_VERSION, ResId, Resource, format_resource_id,
+        format_resource_line, group_to_ico, parse_name_id, parse_type_id, read_resources,
+        set_icon_group,
+    },
+    types::{Action, WresArgs, WresCommand},
+    util,
+    version_info::{format_version_json, format_version_text, parse_version_info},
+};
+
+pub fn run_from_args(program_path: &str, argv: &[String]) -> i32 {
+    let program_name = util::program_basename(program_path);
+
+    let (action, parsed) = match parse_args(argv) {
+        Ok(v) => v,
+        Err(msg) => {
+            eprintln!("{msg}");
+            return 1;
+        }
+    };
+
+    match action {
+        Action::Help => {
+            print_help(&program_name);
+            0
+        }
+        Action::Version => {
+            crate::cli::print_version(&program_name);
+            0
+        }
+        Action::Run => {
+            let Some(parsed) = parsed else {
+                eprintln!("missing argument");
+                print_help(&program_name);
+                return 1;
+            };
+            match parsed.command {
+                WresCommand::List => run_list(&parsed),
+                WresCommand::Extract => run_extract(&parsed),
+                WresCommand::VersionInfo => run_version_info(&parsed),
+                WresCommand::SetIcon => run_set_icon(&parsed),
+            }
+        }
+    }
+}
+
+pub fn parse_args(argv: &[String]) -> Result<(Action, Option<WresArgs>), String> {
+    let mut command: Option<WresCommand> = None;
+    let mut output: Option<String> = None;
+    let mut type_id: Option<ResId> = None;
+    let mut name_id: Option<ResId> = None;
+    let mut language: Option<u16> = None;
+    let mut all = false;
+    let mut raw = false;
+    let mut json = false;
+    let mut icon: Option<String> = None;
+    let mut no_clobber = false;
+    let mut files: Vec<String> = Vec::new();
+
+    let mut i = 0usize;
+    while i < argv.len() {
+        let arg = &argv[i];
+
+        if arg == "--" {
+            files.extend(argv[i + 1..].iter().cloned());
+            break;
+        }
+
+        if arg == "-" || !arg.starts_with('-') {
+            files.push(arg.clone());
+            i += 1;
+            continue;
+        }
+
+        if let Some(stripped) = arg.strip_prefix("--") {
+            let (name, value) = util::split_long(stripped);
+            match name {
+                "help" => return Ok((Action::Help, None)),
+                "version" => return Ok((Action::Version, None)),
+                "list" => util::set_command(&mut command, WresCommand::List)?,
+                "extract" => util::set_command(&mut command, WresCommand::Extract)?,
+                "output" => output = Some(util::take_value(value, argv, &mut i, "--output")?),
+                "type" => {
+                    type_id = Some(parse_type_id(&util::take_value(
+                        value, argv, &mut i, "--type",
+                    )?))
+                }
+                "name" => {
+                    name_id = Some(parse_name_id(&util::take_value(
+                        value, argv, &mut i, "--name",
+                    )?))
+                }
+                "language" => {
+                    language = Some(parse_language(&util::take_value(
+                        value,
+                        argv,
+                        &mut i,
+                        "--language",
+                    )?)?)
+                }
+                "version-info" => util::set_command(&mut command, WresCommand::VersionInfo)?,
+                "set-icon" => {
+                    util::set_command(&mut command, WresCommand::SetIcon)?;
+                    icon = Some(util::take_value(value, argv, &mut i, "--set-icon")?);
+                }
+                "all" => all = true,
+                "raw" => raw = true,
+                "json" => json = true,
+                "no-clobber" => no_clobber = true,
+                _ => return Err(format!("unrecognized option '--{name}'")),
+            }
+
+            i += 1;
+            continue;
+        }
+
+        let mut chars = arg[1..].chars().peekable();
+        while let Some(ch) = chars.next() {
+            match ch {
+                'l' => util::set_command(&mut command, WresCommand::List)?,
+                'x' => util::set_command(&mut command, WresCommand::Extract)?,
+                'o' => output = Some(util::take_short_value(&mut chars, argv, &mut i, "-o")?),
+                't' => {
+                    type_id = Some(parse_type_id(&util::take_short_value(
+                        &mut chars, argv, &mut i, "-t",
+                    )?))
+                }
+                'n' => {
+                    name_id = Some(parse_name_id(&util::take_short_value(
+                        &mut chars, argv, &mut i, "-n",
+                    )?))
+                }
+                'L' => {
+                    language = Some(parse_language(&util::take_short_value(
+                        &mut chars, argv, &mut i, "-L",
+                    )?)?)
+                }
+                'a' => all = true,
+                'R' => raw = true,
+                _ => return Err(format!("invalid option -- '{ch}'")),
+            }
+        }
+
+        i += 1;
+    }
+
+    if files.is_empty() {
+        return Ok((Action::Run, None));
+    }
+
+    Ok((
+        Action::Run,
+        Some(WresArgs {
+            command: command.unwrap_or(WresCommand::List),
+            output,
+            type_id,
+            name: name_id,
+            language,
+            all,
+            raw,
+            json,
+            icon,
+            no_clobber,
+            files,
+        }),
+    ))
+}
+
+fn parse_language(value: &str) -> Result<u16, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid language value: {value}"))
+}
+
+pub fn print_help(program_name: &str) {
+    println!("Usage: {program_name} [OPTION]... [FILE]...");
+    println!("Extract or list resources from Microsoft Windows(R) binaries.");
+    println!();
+    println!("Commands:");
+    println!("  -x, --extract                extract resources");
+    println!("  -l, --list                   output list of resources (default)");
+    println!("      --version-info           print version resources as KEY=VALUE lines");
+    println!("      --set-icon=FILE          store the icons of FILE as an icon group");
+    println!("      --help                   display this help and exit");
+    println!("      --version                output version information and exit");
+    println!();
+    println!("Options:");
+    println!("  -t, --type=ID                match resource type (number or name, e.g. icon)");
+    println!("  -n, --name=ID                match resource name");
+    println!("  -L, --language=ID            match resource language");
+    println!("  -a, --all                    select all resources, not only convertible ones");
+    println!("  -R, --raw                    extract resource data without conversion");
+    println!("      --json                   print version resources as JSON");
+    println!("  -o, --output=PATH            where to place extracted files, or the");
+    println!("                               modified executable (default is in place)");
+    println!("      --no-clobber             do not overwrite existing files");
+    println!();
+}
+
+fn run_list(args: &WresArgs) -> i32 {
+    let mut status = 0;
+    for file in &args.files {
+        let display_name = if file == "-" {
+            "(standard in)"
+        } else {
+            file.as_str()
+        };
+
+        let bytes = match read_input(file) {
+            Ok(bytes) => bytes,
+            Err(_) => {
+                eprintln!("{file}: cannot open file");
+                status = 1;
+                continue;
+            }
+        };
+
+        match read_resources(&bytes) {
+            Ok(resources) => {
+                for res in resources.iter().filter(|r| matches_filters(args, r)) {
+                    println!("{}", format_resource_line(res));
+                }
+            }
+            Err(msg) => {
+                eprintln!("{display_name}: {msg}");
+                status = 1;
+            }
+        }
+    }
+    status
+}
+
+fn run_extract(args: &WresArgs) -> i32 {
+    let output_is_dir = args
+        .output
+        .as_deref()
+        .and_then(|o| std::fs::metadata(o).ok())
+        .is_some_and(|m| m.is_dir());
+
+    let mut names = OutputNames::default();
+    let mut status = 0;
+    for file in &args.files {
+        let display_name = if file == "-" {
+            "(standard in)"
+        } else {
+            file.as_str()
+        };
+
+        let bytes = match read_input(file) {
+            Ok(bytes) => bytes,
+            Err(_) => {
+                eprintln!("{file}: cannot open file");
+                status = 1;
+                continue;
+            }
+        };
+
+        let resources = match read_resources(&bytes) {
+            Ok(resources) => resources,
+            Err(msg) => {
+                eprintln!("{display_name}: {msg}");
+                status = 1;
+                continue;
+            }
+        };
+
+        for res in resources.iter().filter(|r| matches_filters(args, r)) {
+            let (data, ext) = match extract_resource(res, &resources, args) {
+                Ok(Some(v)) => v,
+                Ok(None) => continue,
+                Err(msg) => {
+                    eprintln!("{display_name}: {msg}");
+                    status = 1;
+                    continue;
+                }
+            };
+            let outname = match args.output.as_deref() {
+                Some(dir) if output_is_dir => std::path::Path::new(dir)
+                    .join(gen_extract_name(display_name, res, ext))
+                    .to_string_lossy()
+                    .to_string(),
+                Some(path) if path != "-" => path.to_string(),
+                _ => {
+                    if io::stdout().lock().write_all(&data).is_err() {
+                        eprintln!("(standard out): cannot write to file");
+                        return 1;
+                    }
+                    continue;
+                }
+            };
+            // Several matches written to one -o FILE get numbered names.
+            let outname = names.claim(&outname);
+            match write_file(&outname, &data, args.no_clobber) {
+                Ok(()) => {}
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    eprintln!("{outname}: file exists, skipping");
+                }
+                Err(_) => {
+                    eprintln!("{outname}: cannot create file");
+                    status = 1;
+                }
+            }
+        }
+    }
+    status
+}
+
+fn run_version_info(args: &WresArgs) -> i32 {
+    let mut status = 0;
+    for file in &args.files {
+        let display_name = if file == "-" {
+            "(standard in)"
+        } else {
+            file.as_str()
+        };
+
+        let bytes = match read_input(file) {
+            Ok(bytes) => bytes,
+            Err(_) => {
+                eprintln!("{file}: cannot open file");
+                status = 1;
+                continue;
+            }
+        };
+
+        let resources = match read_resources(&bytes) {
+            Ok(resources) => resources,
+            Err(msg) => {
+                eprintln!("{display_name}: {msg}");
+                status = 1;
+                continue;
+            }
+        };
+
+        let mut found = false;
+        for res in resources
+            .iter()
+            .filter(|r| r.type_id == ResId::Id(RT_VERSION) && matches_filters(args, r))
+        {
+            found = true;
+            match parse_version_info(&res.data) {
+                Ok(info) if args.json => println!("{}", format_version_json(&info)),
+                Ok(info) => print!("{}", format_version_text(&info)),
+                Err(msg) => {
+                    eprintln!("{display_name}: {}: {msg}", format_resource_id(res));
+                    status = 1;
+                }
+            }
+        }
+        if !found {
+            eprintln!("{display_name}: no version resource found");
+            status = 1;
+        }
+    }
+    status
+}
+
+fn run_set_icon(args: &WresArgs) -> i32 {
+    let [file] = args.files.as_slice() else {
+        eprintln!("--set-icon takes exactly one executable");
+        return 1;
+    };
+    let icon_path = args.icon.as_deref().unwrap_or_default();
+    let Ok(icon) = read_input(icon_path) else {
+        eprintln!("{icon_path}: cannot open file");
+        return 1;
+    };
+    let Ok(bytes) = read_input(file) else {
+        eprintln!("{file}: cannot open file");
+        return 1;
+    };
+    if !is_pe(&bytes) {
+        eprintln!("{file}: not a PE file");
+        return 1;
+    }
+
+    let result = read_resources(&bytes).and_then(|mut resources| {
+        set_icon_group(&mut resources, &icon, args.name.clone(), args.language)?;
+        replace_pe_resources(&bytes, &resources)
+    });
+    let rewritten = match result {
+        Ok(rewritten) => rewritten,
+        Err(msg) => {
+            eprintln!("{file}: {msg}");
+            return 1;
+        }
+    };
+    if rewritten.signature_removed {
+        eprintln!("{file}: warning: removed Authenticode signature");
+    }
+
+    let outname = args.output.as_deref().unwrap_or(file);
+    if outname == "-" {
+        if io::stdout().lock().write_all(&rewritten.bytes).is_err() {
+            eprintln!("(standard out): cannot write to file");
+            return 1;
+        }
+    } else {
+        match write_file(outname, &rewritten.bytes, args.no_clobber) {
+            Ok(()) => {}
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                eprintln!("{outname}: file exists");
+                return 1;
+            }
+            Err(_) => {
+                eprintln!("{outname}: cannot create file");
+                return 1;
+            }
+        }
+    }
+    0
+}
+
+/// File contents and extension of an extracted resource.
+type Extracted = (Vec<u8>, Option<&'static str>);
+
+/// Returns the data to write for `res` and the extension of its file, or
+/// `None` if the resource is skipped.
+fn extract_resource(
+    res: &Resource,
+    resources: &[Resource],
+    args: &WresArgs,
+) -> Result<Option<Extracted>, String> {
+    if args.raw {
+        return Ok(Some((res.data.clone(), None)));
+    }
+    match res.type_id {
+        ResId::Id(RT_GROUP_ICON) => Ok(Some((group_to_ico(res, resources)?, Some("ico")))),
+        ResId::Id(RT_GROUP_CURSOR) => Ok(Some((group_to_ico(res, resources)?, Some("cur")))),
+        ResId::Id(RT_BITMAP) => Ok(Some((dib_to_bmp_file(&res.data)?, Some("bmp")))),
+        // Without an explicit selection, only convertible resources are wanted.
+        _ if !is_selective(args) => Ok(None),
+        _ => Err(format!(
+            "{}: don't know how to extract resource, try `--raw'",
+            format_resource_id(res)
+        )),
+    }
+}
+
+fn is_selective(args: &WresArgs) -> bool {
+    args.all || args.type_id.is_some() || args.name.is_some() || args.language.is_some()
+}
+
+fn matches_filters(args: &WresArgs, res: &Resource) -> bool {
+    if args.type_id.as_ref().is_some_and(|t| *t != res.type_id) {
+        return false;
+    }
+    if args.name.as_ref().is_some_and(|n| *n != res.name) {
+        return false;
+    }
+    if args.language.is_some() && args.language != res.language {
+        return false;
+    }
+    true
+}
+
+/// Names an extracted resource `BASENAME_TYPE_NAME[_LANGUAGE][.EXT]`.
+fn gen_extract_name(inname: &str, res: &Resource, ext: Option<&str>) -> String {
+    let base = util::program_basename(inname);
+    let mut name = format!(
+        "{base}_{}_{}",
+        id_string(&res.type_id),
+        id_string(&res.name)
+    );
+    if let Some(lang) = res.language {
+        name.push_str(&format!("_{lang}"));
+    }
+    if let Some(ext) = ext {
+        name.push('.');
+        name.push_str(ext);
+    }
+    name
+}
+
+/// Formats a resource id for use in a file name. Names come from the input
+/// file, so path separators, drive colons and control characters are replaced
+/// to keep the file inside the output directory.
+fn id_string(id: &ResId) -> String {
+    match id {
+        ResId::Id(n) => n.to_string(),
+        ResId::Name(s) => {
+            let mut name: String = s
+                .chars()
+                .map(|c| match c {
+                    '/' | '\\' | ':' => '_',
+                    c if c.is_control() => '_',
+                    c => c,
+                })
+                .collect();
+            while name.contains("..") {
+                name = name.replace("..", "_");
+            }
+            name
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(argv: &[&str]) -> WresArgs {
+        let argv: Vec<String> = argv.iter().map(|s| s.to_string()).collect();
+        parse_args(&argv).unwrap().1.unwrap()
+    }
+
+    fn resource(type_id: u16, data: Vec<u8>) -> Resource {
+        Resource {
+            type_id: ResId::Id(type_id),
+            name: ResId::Name("LOGO".to_string()),
+            language: Some(1033),
+            offset: 0,
+            data,
+        }
+    }
+
+    #[test]
+    fn filters_select_by_type_name_and_language() {
+        let args = parse(&["-x", "--type=bitmap", "-nLOGO", "-L", "1033", "app.exe"]);
+        assert!(matches_filters(&args, &resource(RT_BITMAP, Vec::new())));
+        assert!(!matches_filters(&args, &resource(24, Vec::new())));
+
+        let args = parse(&["-x", "-t", "24", "app.exe"]);
+        let manifest = resource(24, b"<assembly/>".to_vec());
+        assert!(extract_resource(&manifest, &[], &args).is_err());
+
+        let args = parse(&["-xR", "-t", "24", "app.exe"]);
+        let (data, ext) = extract_resource(&manifest, &[], &args).unwrap().unwrap();
+        assert_eq!(data, manifest.data);
+        assert_eq!(
+            gen_extract_name("dir/app.exe", &manifest, ext),
+            "app.exe_24_LOGO_1033"
+        );
+
+        let mut evil = manifest.clone();
+        evil.name = ResId::Name("../..\\c:/x".to_string());
+        assert_eq!(
+            gen_extract_name("app.exe", &evil, None),
+            "app.exe_24_____c__x_1033"
+        );
+    }
+
+    #[test]
+    fn bitmap_resources_get_a_file_header() {
+        // 1x1 24-bit DIB with a 40-byte header and one padded row.
+        let mut dib = vec![0u8; 40];
+        dib[0] = 40;
+        dib[4] = 1;
+        dib[8] = 1;
+        dib[12] = 1;
+        dib[14] = 24;
+        dib.extend([255, 0, 0, 0]);
+
+        let args = parse(&["-x", "app.exe"]);
+        let bitmap = resource(RT_BITMAP, dib);
+        let (data, ext) = extract_resource(&bitmap, &[], &args).unwrap().unwrap();
+        assert_eq!(ext, Some("bmp"));
+        assert_eq!(&data[0..2], b"BM");
+        assert_eq!(u32::from_le_bytes(data[2..6].try_into().unwrap()), 58);
+        assert_eq!(u32::from_le_bytes(data[10..14].try_into().unwrap()), 54);
+        let (w, h, rgba) = crate::dib::decode_bmp_file(&data).unwrap();
+        assert_eq!((w, h, rgba), (1, 1, vec![0, 0, 255, 255]));
+    }
+}